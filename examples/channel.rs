@@ -1,21 +1,11 @@
-use std::sync::mpsc::channel;
-use std::thread;
-use win_binder::listen;
+use win_binder::events;
 
 fn main() {
-    // spawn new thread because listen blocks
-    let (schan, rchan) = channel();
-    let _listener = thread::spawn(move || {
-        listen(move |event| {
-            schan
-                .send(event)
-                .unwrap_or_else(|e| println!("Could not send event {:?}", e));
-        })
-        .expect("Could not listen");
-    });
+    // The listener runs on a thread managed by the receiver.
+    let rchan = events().expect("Could not listen");
 
     let mut events = Vec::new();
-    for event in rchan.iter() {
+    for event in rchan {
         println!("Received {:?}", event);
         events.push(event);
     }
@@ -0,0 +1,29 @@
+use std::fs::File;
+use std::thread;
+use std::time::Duration;
+use win_binder::{events, play, read_recording, RecordFormat, Recorder, Speed};
+
+fn main() {
+    let path = "session.jsonl";
+
+    println!("Recording for 5 seconds...");
+    let mut recorder = Recorder::new(
+        File::create(path).expect("Could not create recording"),
+        RecordFormat::JsonLines,
+    )
+    .expect("Could not write recording");
+    let rchan = events().expect("Could not listen");
+    thread::sleep(Duration::from_secs(5));
+    while let Ok(event) = rchan.try_recv() {
+        recorder.record(&event).expect("Could not write recording");
+    }
+    drop(rchan);
+    recorder.flush().expect("Could not write recording");
+
+    println!("Replaying twice as fast...");
+    let events = read_recording(File::open(path).expect("Could not open recording"))
+        .expect("Could not read recording");
+    play(events, Speed::Factor(2.0))
+        .wait()
+        .expect("Could not replay recording");
+}
@@ -0,0 +1,17 @@
+use win_binder::{grab, Keymap, Remapper};
+
+fn main() {
+    let keymap = Keymap::from_toml(
+        r#"
+        CapsLock = "Escape"
+        "Ctrl+H" = "Backspace"
+        "#,
+    )
+    .expect("Invalid keymap");
+    let mut remapper = Remapper::new(keymap);
+
+    // This will block.
+    if let Err(error) = grab(move |event| remapper.filter(event)) {
+        println!("Error: {:?}", error)
+    }
+}
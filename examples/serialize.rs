@@ -1,11 +1,13 @@
 use std::time::SystemTime;
-use win_binder::{Event, EventType, Key};
+use win_binder::{Event, EventType, Key, Modifiers};
 
 fn main() {
     let event = Event {
         event_type: EventType::KeyPress(Key::KeyS),
         time: SystemTime::now(),
         name: Some(String::from("S")),
+        modifiers: Modifiers::empty(),
+        raw: None,
     };
 
     let serialized = serde_json::to_string(&event).unwrap();
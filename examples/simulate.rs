@@ -1,12 +1,12 @@
 use std::{thread, time};
-use win_binder::{simulate, Button, EventType, Key, SimulateError};
+use win_binder::{simulate, Button, EventType, Key, WheelUnit};
 
 fn send(event_type: &EventType) {
     let delay = time::Duration::from_millis(20);
     match simulate(event_type) {
         Ok(()) => (),
-        Err(SimulateError) => {
-            println!("We could not send {:?}", event_type);
+        Err(error) => {
+            println!("We could not send {:?}: {}", event_type, error);
         }
     }
     // Let ths OS catchup (at least MacOS)
@@ -24,5 +24,6 @@ fn main() {
     send(&EventType::Wheel {
         delta_x: 0,
         delta_y: 1,
+        unit: WheelUnit::Notches,
     });
 }
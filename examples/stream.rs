@@ -0,0 +1,17 @@
+use futures_core::Stream;
+use std::future::poll_fn;
+use std::pin::Pin;
+use win_binder::{listen_stream_with, OverflowPolicy, StreamOptions};
+
+#[tokio::main]
+async fn main() {
+    let options = StreamOptions {
+        capacity: 256,
+        overflow: OverflowPolicy::DropOldest,
+    };
+    let mut events = listen_stream_with(options).expect("Could not listen");
+
+    while let Some(event) = poll_fn(|cx| Pin::new(&mut events).poll_next(cx)).await {
+        println!("Received {:?}", event);
+    }
+}
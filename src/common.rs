@@ -1,7 +1,12 @@
 use crate::keyboard::Keyboard;
-use crate::keycodes::key_from_code;
+use crate::keycodes::code_from_key;
+use crate::keycodes::key_from_hook;
+use crate::modifiers::Modifiers;
 use crate::win_binder::Button;
+use crate::win_binder::EventOrigin;
 use crate::win_binder::EventType;
+use crate::win_binder::RawEventInfo;
+use crate::win_binder::WheelUnit;
 use std::os::raw::c_int;
 use std::os::raw::c_long;
 use std::os::raw::c_short;
@@ -13,14 +18,27 @@ use std::sync::LazyLock;
 use std::sync::Mutex;
 use windows::Win32::Foundation::GetLastError;
 use windows::Win32::Foundation::HINSTANCE;
+use windows::Win32::Foundation::HWND;
 use windows::Win32::Foundation::LPARAM;
 use windows::Win32::Foundation::LRESULT;
 use windows::Win32::Foundation::WPARAM;
+use windows::Win32::UI::Input::KeyboardAndMouse::GetAsyncKeyState;
+use windows::Win32::UI::Input::KeyboardAndMouse::GetKeyState;
+use windows::Win32::UI::WindowsAndMessaging::GetMessageA;
+use windows::Win32::UI::WindowsAndMessaging::PeekMessageA;
 use windows::Win32::UI::WindowsAndMessaging::SetWindowsHookExA;
+use windows::Win32::UI::WindowsAndMessaging::UnhookWindowsHookEx;
 use windows::Win32::UI::WindowsAndMessaging::HHOOK;
 use windows::Win32::UI::WindowsAndMessaging::KBDLLHOOKSTRUCT;
+use windows::Win32::UI::WindowsAndMessaging::LLKHF_ALTDOWN;
+use windows::Win32::UI::WindowsAndMessaging::LLKHF_EXTENDED;
+use windows::Win32::UI::WindowsAndMessaging::LLKHF_INJECTED;
+use windows::Win32::UI::WindowsAndMessaging::LLKHF_LOWER_IL_INJECTED;
+use windows::Win32::UI::WindowsAndMessaging::LLMHF_INJECTED;
+use windows::Win32::UI::WindowsAndMessaging::LLMHF_LOWER_IL_INJECTED;
+use windows::Win32::UI::WindowsAndMessaging::MSG;
 use windows::Win32::UI::WindowsAndMessaging::MSLLHOOKSTRUCT;
-use windows::Win32::UI::WindowsAndMessaging::WHEEL_DELTA;
+use windows::Win32::UI::WindowsAndMessaging::PM_NOREMOVE;
 use windows::Win32::UI::WindowsAndMessaging::WH_KEYBOARD_LL;
 use windows::Win32::UI::WindowsAndMessaging::WH_MOUSE_LL;
 use windows::Win32::UI::WindowsAndMessaging::WM_KEYDOWN;
@@ -48,8 +66,6 @@ pub type WORD = c_ushort;
 pub(crate) static KEYBOARD: LazyLock<Mutex<Keyboard>> =
     LazyLock::new(|| Mutex::new(Keyboard::new().expect("Failed to create Keyboard")));
 
-pub static mut HOOK: HHOOK = HHOOK(null_mut());
-
 #[inline]
 #[allow(non_snake_case)]
 pub fn HIWORD(l: DWORD) -> WORD {
@@ -81,16 +97,78 @@ pub unsafe fn get_button_code(lpdata: LPARAM) -> WORD {
     HIWORD(mouse.mouseData)
 }
 
+/// `signature` is the one written by this process into `dwExtraInfo`, and
+/// `signature + 1` the one of replacement events.
+pub fn origin(injected: bool, extra_info: usize, signature: usize) -> EventOrigin {
+    if !injected {
+        EventOrigin::Hardware
+    } else if extra_info == signature {
+        EventOrigin::SelfInjected
+    } else if extra_info == signature.wrapping_add(1) {
+        EventOrigin::Replacement
+    } else {
+        EventOrigin::Injected
+    }
+}
+
+pub fn keyboard_info(kb: &KBDLLHOOKSTRUCT, signature: usize) -> RawEventInfo {
+    let flags = kb.flags.0;
+    let injected = flags & LLKHF_INJECTED.0 != 0;
+    RawEventInfo {
+        scan_code: kb.scanCode,
+        extended: flags & LLKHF_EXTENDED.0 != 0,
+        injected,
+        lower_il_injected: flags & LLKHF_LOWER_IL_INJECTED.0 != 0,
+        alt_down: flags & LLKHF_ALTDOWN.0 != 0,
+        extra_info: kb.dwExtraInfo,
+        time: kb.time,
+        origin: origin(injected, kb.dwExtraInfo, signature),
+    }
+}
+
+pub fn mouse_info(mouse: &MSLLHOOKSTRUCT, signature: usize) -> RawEventInfo {
+    let injected = mouse.flags & LLMHF_INJECTED != 0;
+    RawEventInfo {
+        scan_code: 0,
+        extended: false,
+        injected,
+        lower_il_injected: mouse.flags & LLMHF_LOWER_IL_INJECTED != 0,
+        alt_down: false,
+        extra_info: mouse.dwExtraInfo,
+        time: mouse.time,
+        origin: origin(injected, mouse.dwExtraInfo, signature),
+    }
+}
+
+pub unsafe fn get_raw_info(param: WPARAM, lpdata: LPARAM, signature: usize) -> RawEventInfo {
+    match param.0.try_into() {
+        Ok(WM_KEYDOWN) | Ok(WM_SYSKEYDOWN) | Ok(WM_KEYUP) | Ok(WM_SYSKEYUP) => {
+            keyboard_info(&*(lpdata.0 as *const KBDLLHOOKSTRUCT), signature)
+        }
+        _ => mouse_info(&*(lpdata.0 as *const MSLLHOOKSTRUCT), signature),
+    }
+}
+
+/// The modifiers held and the lock keys toggled on right now, to seed the
+/// tracking of a new hook.
+pub unsafe fn current_modifiers() -> Modifiers {
+    let code = |key| code_from_key(key).map_or(0, i32::from);
+    Modifiers::from_state(
+        |key| GetAsyncKeyState(code(key)) < 0,
+        |key| GetKeyState(code(key)) & 1 != 0,
+    )
+}
+
 pub unsafe fn convert(param: WPARAM, lpdata: LPARAM) -> Option<EventType> {
     match param.0.try_into() {
         Ok(WM_KEYDOWN) | Ok(WM_SYSKEYDOWN) => {
-            let code = get_code(lpdata);
-            let key = key_from_code(code as u16);
+            let kb = *(lpdata.0 as *const KBDLLHOOKSTRUCT);
+            let key = key_from_hook(kb.vkCode as u16, kb.flags.0 & LLKHF_EXTENDED.0 != 0);
             Some(EventType::KeyPress(key))
         }
         Ok(WM_KEYUP) | Ok(WM_SYSKEYUP) => {
-            let code = get_code(lpdata);
-            let key = key_from_code(code as u16);
+            let kb = *(lpdata.0 as *const KBDLLHOOKSTRUCT);
+            let key = key_from_hook(kb.vkCode as u16, kb.flags.0 & LLKHF_EXTENDED.0 != 0);
             Some(EventType::KeyRelease(key))
         }
         Ok(WM_LBUTTONDOWN) => Some(EventType::ButtonPress(Button::Left)),
@@ -100,12 +178,12 @@ pub unsafe fn convert(param: WPARAM, lpdata: LPARAM) -> Option<EventType> {
         Ok(WM_RBUTTONDOWN) => Some(EventType::ButtonPress(Button::Right)),
         Ok(WM_RBUTTONUP) => Some(EventType::ButtonRelease(Button::Right)),
         Ok(WM_XBUTTONDOWN) => {
-            let code = get_button_code(lpdata) as u8;
-            Some(EventType::ButtonPress(Button::Unknown(code)))
+            let button = Button::from_x_button(get_button_code(lpdata));
+            Some(EventType::ButtonPress(button))
         }
         Ok(WM_XBUTTONUP) => {
-            let code = get_button_code(lpdata) as u8;
-            Some(EventType::ButtonRelease(Button::Unknown(code)))
+            let button = Button::from_x_button(get_button_code(lpdata));
+            Some(EventType::ButtonRelease(button))
         }
         Ok(WM_MOUSEMOVE) => {
             let (x, y) = get_point(lpdata);
@@ -118,14 +196,16 @@ pub unsafe fn convert(param: WPARAM, lpdata: LPARAM) -> Option<EventType> {
             let delta = get_delta(lpdata) as c_short;
             Some(EventType::Wheel {
                 delta_x: 0,
-                delta_y: (delta / WHEEL_DELTA as i16) as i64,
+                delta_y: delta.into(),
+                unit: WheelUnit::Raw,
             })
         }
         Ok(WM_MOUSEHWHEEL) => {
             let delta = get_delta(lpdata) as c_short;
             Some(EventType::Wheel {
-                delta_x: (delta / WHEEL_DELTA as i16) as i64,
+                delta_x: delta.into(),
                 delta_y: 0,
+                unit: WheelUnit::Raw,
             })
         }
         _ => None,
@@ -137,25 +217,153 @@ type RawCallback = unsafe extern "system" fn(code: c_int, param: WPARAM, lpdata:
 pub enum HookError {
     Mouse(DWORD),
     Key(DWORD),
+    /// Subscribing from inside a subscriber of the same thread.
+    Reentrant,
 }
 
-pub unsafe fn set_key_hook(callback: RawCallback) -> Result<(), HookError> {
+pub unsafe fn set_key_hook(callback: RawCallback) -> Result<HHOOK, HookError> {
     let hook = SetWindowsHookExA(WH_KEYBOARD_LL, Some(callback), HINSTANCE(null_mut()), 0);
 
     if hook.is_err() {
         let error = GetLastError();
         return Err(HookError::Key(error.0));
     }
-    HOOK = hook.unwrap();
-    Ok(())
+    Ok(hook.unwrap())
 }
 
-pub unsafe fn set_mouse_hook(callback: RawCallback) -> Result<(), HookError> {
+pub unsafe fn set_mouse_hook(callback: RawCallback) -> Result<HHOOK, HookError> {
     let hook = SetWindowsHookExA(WH_MOUSE_LL, Some(callback), HINSTANCE(null_mut()), 0);
     if hook.is_err() {
         let error = GetLastError();
         return Err(HookError::Mouse(error.0));
     }
-    HOOK = hook.unwrap();
-    Ok(())
+    Ok(hook.unwrap())
+}
+
+/// The keyboard and mouse hooks installed by one listener. Both are
+/// unhooked when this is dropped, which must happen on the installing thread.
+pub struct Hooks {
+    keyboard: HHOOK,
+    mouse: HHOOK,
+}
+
+impl Hooks {
+    pub unsafe fn install(callback: RawCallback) -> Result<Hooks, HookError> {
+        let keyboard = set_key_hook(callback)?;
+        let mouse = match set_mouse_hook(callback) {
+            Ok(mouse) => mouse,
+            Err(error) => {
+                let _ = UnhookWindowsHookEx(keyboard);
+                return Err(error);
+            }
+        };
+        Ok(Hooks { keyboard, mouse })
+    }
+}
+
+impl Drop for Hooks {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = UnhookWindowsHookEx(self.keyboard);
+            let _ = UnhookWindowsHookEx(self.mouse);
+        }
+    }
+}
+
+/// Forces the creation of the current thread's message queue, so that
+/// `PostThreadMessageA` can reach it before `message_loop` is entered.
+pub unsafe fn ensure_message_queue() {
+    let mut msg = MSG::default();
+    let _ = PeekMessageA(&mut msg, HWND(null_mut()), 0, 0, PM_NOREMOVE);
+}
+
+/// Pumps messages so the low level hooks get called, until WM_QUIT is
+/// received or GetMessageA fails.
+pub unsafe fn message_loop() {
+    let mut msg = MSG::default();
+    while GetMessageA(&mut msg, HWND(null_mut()), 0, 0).0 > 0 {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::Win32::Foundation::POINT;
+    use windows::Win32::UI::WindowsAndMessaging::KBDLLHOOKSTRUCT_FLAGS;
+
+    #[test]
+    fn test_keyboard_info() {
+        let kb = KBDLLHOOKSTRUCT {
+            vkCode: 0x0D,
+            scanCode: 0x1C,
+            flags: KBDLLHOOKSTRUCT_FLAGS(LLKHF_EXTENDED.0 | LLKHF_INJECTED.0),
+            time: 1234,
+            dwExtraInfo: 42,
+        };
+        assert_eq!(
+            keyboard_info(&kb, 0x5749_4E42),
+            RawEventInfo {
+                scan_code: 0x1C,
+                extended: true,
+                injected: true,
+                lower_il_injected: false,
+                alt_down: false,
+                extra_info: 42,
+                time: 1234,
+                origin: EventOrigin::Injected,
+            }
+        );
+
+        let kb = KBDLLHOOKSTRUCT {
+            vkCode: 0x41,
+            scanCode: 0x1E,
+            flags: LLKHF_ALTDOWN,
+            time: 5,
+            dwExtraInfo: 0,
+        };
+        let info = keyboard_info(&kb, 0);
+        assert!(info.alt_down);
+        assert!(!info.extended);
+        assert!(!info.injected);
+        assert_eq!(info.origin, EventOrigin::Hardware);
+    }
+
+    #[test]
+    fn test_mouse_info() {
+        let mouse = MSLLHOOKSTRUCT {
+            pt: POINT { x: 10, y: 20 },
+            mouseData: 0,
+            flags: LLMHF_INJECTED | LLMHF_LOWER_IL_INJECTED,
+            time: 99,
+            dwExtraInfo: 7,
+        };
+        assert_eq!(
+            mouse_info(&mouse, 7),
+            RawEventInfo {
+                scan_code: 0,
+                extended: false,
+                injected: true,
+                lower_il_injected: true,
+                alt_down: false,
+                extra_info: 7,
+                time: 99,
+                origin: EventOrigin::SelfInjected,
+            }
+        );
+    }
+
+    #[test]
+    fn test_origin() {
+        let signature = 0x5749_4E42;
+        assert_eq!(origin(false, signature, signature), EventOrigin::Hardware);
+        assert_eq!(origin(true, 0, signature), EventOrigin::Injected);
+        assert_eq!(
+            origin(true, signature, signature),
+            EventOrigin::SelfInjected
+        );
+        assert_eq!(
+            origin(true, signature + 1, signature),
+            EventOrigin::Replacement
+        );
+        assert_eq!(origin(true, 0, usize::MAX), EventOrigin::Replacement);
+    }
 }
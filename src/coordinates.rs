@@ -0,0 +1,169 @@
+use crate::win_binder::EventType;
+use crate::win_binder::Rect;
+
+/// `MOUSEEVENTF_ABSOLUTE` coordinates split the virtual screen in 65536 steps
+/// per axis, which Windows turns back into pixels with
+/// `left + absolute * width / 65536`.
+const ABSOLUTE_STEPS: i64 = 65536;
+
+/// The smallest absolute coordinate landing on the pixel at `offset` from
+/// the start of an axis of `length` pixels. Computed with i64 so large
+/// desktops can't overflow.
+fn axis_to_absolute(offset: i64, length: i64) -> i32 {
+    let offset = offset.clamp(0, length - 1);
+    // Rounded up, rounding down could land on the previous pixel. Past 65536
+    // pixels not every pixel can be reached, the last step is the closest.
+    ((offset * ABSOLUTE_STEPS + length - 1) / length).min(ABSOLUTE_STEPS - 1) as i32
+}
+
+/// Maps a point in virtual screen pixels, whose origin is the top left
+/// corner of the primary monitor, to the absolute coordinates of
+/// `MOUSEEVENTF_ABSOLUTE | MOUSEEVENTF_VIRTUALDESK`. `screen` is the virtual
+/// screen, which starts at negative coordinates when a monitor is left of
+/// or above the primary one. Points off the virtual screen are moved to its
+/// closest edge, `None` if it is empty.
+pub fn to_absolute(x: f64, y: f64, screen: Rect) -> Option<(i32, i32)> {
+    if screen.is_empty() {
+        return None;
+    }
+    let x = (x.round() as i64).saturating_sub(screen.left.into());
+    let y = (y.round() as i64).saturating_sub(screen.top.into());
+    Some((
+        axis_to_absolute(x, screen.width().into()),
+        axis_to_absolute(y, screen.height().into()),
+    ))
+}
+
+/// Turns the successive positions of `MouseMove` events into
+/// `MouseMoveRelative` ones.
+#[derive(Debug, Default)]
+pub struct MotionTracker {
+    last: Option<(f64, f64)>,
+}
+
+impl MotionTracker {
+    /// The move from the previous position to this one. `None` for the first
+    /// position, for a position that didn't change and for other events.
+    pub fn update(&mut self, event_type: &EventType) -> Option<EventType> {
+        let EventType::MouseMove { x, y } = *event_type else {
+            return None;
+        };
+        let (last_x, last_y) = self.last.replace((x, y))?;
+        let dx = (x - last_x).round() as i64;
+        let dy = (y - last_y).round() as i64;
+        if dx == 0 && dy == 0 {
+            return None;
+        }
+        Some(EventType::MouseMoveRelative { dx, dy })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::win_binder::WheelUnit;
+
+    /// What Windows does with the absolute coordinates.
+    fn to_pixels(absolute: (i32, i32), screen: Rect) -> (i32, i32) {
+        let axis = |absolute: i32, start: i32, length: i32| {
+            (i64::from(start) + i64::from(absolute) * i64::from(length) / ABSOLUTE_STEPS) as i32
+        };
+        (
+            axis(absolute.0, screen.left, screen.width()),
+            axis(absolute.1, screen.top, screen.height()),
+        )
+    }
+
+    fn layouts() -> Vec<Rect> {
+        vec![
+            // A single 1920x1080 monitor.
+            Rect::new(0, 0, 1920, 1080),
+            // A 1280x1024 monitor left of it, with its top lower.
+            Rect::new(-1280, 0, 1920, 1224),
+            // A 2560x1440 monitor above and to the left of a 4K one.
+            Rect::new(-800, -1440, 3840, 2160),
+            // A 1366x768 monitor on the right of a 1920x1080 one.
+            Rect::new(0, 0, 3286, 1080),
+        ]
+    }
+
+    #[test]
+    fn test_every_pixel_round_trips() {
+        for screen in layouts() {
+            for x in screen.left..screen.right {
+                let absolute = to_absolute(x as f64, screen.top as f64, screen).unwrap();
+                assert!((0..ABSOLUTE_STEPS as i32).contains(&absolute.0));
+                assert_eq!(to_pixels(absolute, screen).0, x, "{:?}", screen);
+            }
+            for y in screen.top..screen.bottom {
+                let absolute = to_absolute(screen.left as f64, y as f64, screen).unwrap();
+                assert_eq!(to_pixels(absolute, screen).1, y, "{:?}", screen);
+            }
+        }
+    }
+
+    #[test]
+    fn test_negative_origin() {
+        let screen = Rect::new(-1280, 0, 1920, 1224);
+        assert_eq!(to_absolute(-1280.0, 0.0, screen), Some((0, 0)));
+        // The top left corner of the primary monitor.
+        assert_eq!(to_absolute(0.0, 0.0, screen), Some((26215, 0)));
+        assert_eq!(to_pixels((26215, 0), screen), (0, 0));
+    }
+
+    #[test]
+    fn test_clamped_to_screen() {
+        let screen = Rect::new(-800, -1440, 3840, 2160);
+        let top_left = to_absolute(-800.0, -1440.0, screen);
+        let bottom_right = to_absolute(3839.0, 2159.0, screen);
+        assert_eq!(to_absolute(-5000.0, -5000.0, screen), top_left);
+        assert_eq!(to_absolute(1e12, f64::INFINITY, screen), bottom_right);
+        assert_eq!(
+            to_absolute(f64::NAN, 0.0, screen),
+            to_absolute(0.0, 0.0, screen)
+        );
+        assert_eq!(to_pixels(bottom_right.unwrap(), screen), (3839, 2159));
+    }
+
+    #[test]
+    fn test_motion_tracker() {
+        let mut tracker = MotionTracker::default();
+        let mut update = |x, y| tracker.update(&EventType::MouseMove { x, y });
+        assert_eq!(update(100.0, 100.0), None);
+        assert_eq!(
+            update(90.0, 103.0),
+            Some(EventType::MouseMoveRelative { dx: -10, dy: 3 })
+        );
+        assert_eq!(update(90.0, 103.0), None);
+        // Across a monitor left of the primary one.
+        assert_eq!(
+            update(-500.0, 103.0),
+            Some(EventType::MouseMoveRelative { dx: -590, dy: 0 })
+        );
+        assert_eq!(
+            tracker.update(&EventType::Wheel {
+                delta_x: 0,
+                delta_y: 1,
+                unit: WheelUnit::Notches,
+            }),
+            None
+        );
+        assert_eq!(
+            tracker.update(&EventType::MouseMove { x: -500.0, y: 0.0 }),
+            Some(EventType::MouseMoveRelative { dx: 0, dy: -103 })
+        );
+    }
+
+    #[test]
+    fn test_large_desktop() {
+        // Wider than 65536 pixels: some pixels can't be reached, but nothing
+        // overflows and moves land on the closest reachable pixel.
+        let screen = Rect::new(-30000, -20000, 40000, 20000);
+        for x in [-30000, -1, 0, 12345, 39999] {
+            let absolute = to_absolute(x as f64, 0.0, screen).unwrap();
+            assert!((0..ABSOLUTE_STEPS as i32).contains(&absolute.0));
+            assert!((to_pixels(absolute, screen).0 - x).abs() <= 1);
+        }
+        assert_eq!(to_absolute(0.0, 0.0, Rect::default()), None);
+    }
+}
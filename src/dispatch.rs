@@ -0,0 +1,298 @@
+use crate::modifiers::ModifierTracker;
+use crate::modifiers::Modifiers;
+use crate::win_binder::Event;
+use crate::win_binder::EventType;
+
+pub enum Subscriber {
+    /// Sees every event that reaches it, including the ones we injected.
+    Listener(Box<dyn FnMut(Event)>),
+    /// Passes an event on (`Some`), possibly modified, or swallows it (`None`).
+    /// Replacement events we injected ourselves are not handed to grabbers.
+    #[cfg_attr(not(feature = "unstable_grab"), allow(dead_code))]
+    Grabber(Box<dyn FnMut(Event) -> Option<Event>>),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SubscriberId(usize);
+
+#[derive(Debug, PartialEq)]
+pub enum Dispatch {
+    /// Let the event reach the rest of the system.
+    Pass,
+    /// A subscriber swallowed the event.
+    Block,
+    /// A grabber changed the event type: the original must be swallowed and
+    /// this event injected instead.
+    Replace(Event),
+}
+
+/// Hands events to every subscriber of a thread, without touching the OS.
+///
+/// Subscribers are called newest first, the same order the OS uses for hooks
+/// installed with `SetWindowsHookEx`: a grabber started after a listener
+/// sees events first, and once a subscriber swallows an event, the older
+/// subscribers don't see it. Each grabber receives the event as returned by
+/// the previous one. Once a grabber changed the event type, listeners are
+/// skipped: they will see the replacement when it is injected.
+///
+/// It also sets the modifiers of the events. Only the events that reach the
+/// system change them: a swallowed Shift press doesn't leave Shift held.
+#[derive(Default)]
+pub struct Dispatcher {
+    subscribers: Vec<(SubscriberId, Subscriber)>,
+    next_id: usize,
+    modifiers: ModifierTracker,
+}
+
+impl Dispatcher {
+    pub fn subscribe(&mut self, subscriber: Subscriber) -> SubscriberId {
+        let id = SubscriberId(self.next_id);
+        self.next_id += 1;
+        self.subscribers.push((id, subscriber));
+        id
+    }
+
+    pub fn unsubscribe(&mut self, id: SubscriberId) -> bool {
+        let len = self.subscribers.len();
+        self.subscribers.retain(|(other, _)| *other != id);
+        self.subscribers.len() != len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.is_empty()
+    }
+
+    /// Starts tracking the modifiers from this state, typically the one of
+    /// the OS when the hooks are installed.
+    pub fn track_modifiers(&mut self, modifiers: Modifiers) {
+        self.modifiers = ModifierTracker::new(modifiers);
+    }
+
+    /// Tracks the modifiers of an event that was replaced, when the
+    /// replacement couldn't be injected and the original reached the system
+    /// after all.
+    pub fn commit_original(&mut self, original: &EventType) {
+        self.modifiers.update(original);
+    }
+
+    /// `injected` is true for the replacements this process injected itself,
+    /// which only listeners get to see.
+    pub fn dispatch(&mut self, event: Event, injected: bool) -> Dispatch {
+        let original = event.event_type;
+        let mut modifiers = self.modifiers;
+        let mut event = Event {
+            modifiers: modifiers.update(&original),
+            ..event
+        };
+        for (_, subscriber) in self.subscribers.iter_mut().rev() {
+            match subscriber {
+                Subscriber::Listener(_) if event.event_type != original => {}
+                Subscriber::Listener(callback) => callback(event.clone()),
+                Subscriber::Grabber(_) if injected => {}
+                Subscriber::Grabber(callback) => match callback(event) {
+                    Some(next) => event = next,
+                    None => return Dispatch::Block,
+                },
+            }
+        }
+        if event.event_type == original {
+            self.modifiers = modifiers;
+            Dispatch::Pass
+        } else {
+            // The replacement updates the modifiers once injected, or the
+            // original does through `commit_original`.
+            Dispatch::Replace(event)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::win_binder::Key;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::time::SystemTime;
+
+    fn event(key: Key) -> Event {
+        Event {
+            time: SystemTime::now(),
+            name: None,
+            event_type: EventType::KeyPress(key),
+            modifiers: Modifiers::empty(),
+            raw: None,
+        }
+    }
+
+    fn recorder(log: &Arc<Mutex<Vec<(&'static str, Key)>>>, name: &'static str) -> Subscriber {
+        let log = log.clone();
+        Subscriber::Listener(Box::new(move |event: Event| {
+            if let EventType::KeyPress(key) = event.event_type {
+                log.lock().unwrap().push((name, key));
+            }
+        }))
+    }
+
+    fn blocker(blocked: Key) -> Subscriber {
+        Subscriber::Grabber(Box::new(move |event: Event| match event.event_type {
+            EventType::KeyPress(key) if key == blocked => None,
+            _ => Some(event),
+        }))
+    }
+
+    fn remapper(from: Key, to: Key) -> Subscriber {
+        Subscriber::Grabber(Box::new(move |mut event: Event| {
+            if event.event_type == EventType::KeyPress(from) {
+                event.event_type = EventType::KeyPress(to);
+            }
+            Some(event)
+        }))
+    }
+
+    #[test]
+    fn test_dispatch_newest_first() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let mut dispatcher = Dispatcher::default();
+        dispatcher.subscribe(recorder(&log, "first"));
+        dispatcher.subscribe(recorder(&log, "second"));
+
+        assert_eq!(dispatcher.dispatch(event(Key::KeyA), false), Dispatch::Pass);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![("second", Key::KeyA), ("first", Key::KeyA)]
+        );
+    }
+
+    #[test]
+    fn test_block_hides_event_from_older_subscribers() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let mut dispatcher = Dispatcher::default();
+        dispatcher.subscribe(recorder(&log, "listener"));
+        dispatcher.subscribe(blocker(Key::Tab));
+        dispatcher.subscribe(recorder(&log, "late listener"));
+
+        assert_eq!(dispatcher.dispatch(event(Key::Tab), false), Dispatch::Block);
+        assert_eq!(dispatcher.dispatch(event(Key::KeyS), false), Dispatch::Pass);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                ("late listener", Key::Tab),
+                ("late listener", Key::KeyS),
+                ("listener", Key::KeyS)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unsubscribe() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let mut dispatcher = Dispatcher::default();
+        let grabber = dispatcher.subscribe(blocker(Key::Tab));
+        let listener = dispatcher.subscribe(recorder(&log, "listener"));
+        assert_ne!(grabber, listener);
+
+        assert!(dispatcher.unsubscribe(grabber));
+        assert!(!dispatcher.unsubscribe(grabber));
+        assert_eq!(dispatcher.dispatch(event(Key::Tab), false), Dispatch::Pass);
+
+        assert!(dispatcher.unsubscribe(listener));
+        assert!(dispatcher.is_empty());
+        assert_eq!(dispatcher.dispatch(event(Key::Tab), false), Dispatch::Pass);
+        assert_eq!(*log.lock().unwrap(), vec![("listener", Key::Tab)]);
+    }
+
+    #[test]
+    fn test_replace() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let mut dispatcher = Dispatcher::default();
+        dispatcher.subscribe(recorder(&log, "listener"));
+        dispatcher.subscribe(remapper(Key::CapsLock, Key::Escape));
+
+        match dispatcher.dispatch(event(Key::CapsLock), false) {
+            Dispatch::Replace(event) => {
+                assert_eq!(event.event_type, EventType::KeyPress(Key::Escape))
+            }
+            dispatch => panic!("Unexpected {:?}", dispatch),
+        }
+        // Older listeners see neither: the replacement reaches them once injected.
+        assert!(log.lock().unwrap().is_empty());
+
+        // A grabber giving back the same event type passes it through.
+        assert_eq!(dispatcher.dispatch(event(Key::KeyA), false), Dispatch::Pass);
+    }
+
+    #[test]
+    fn test_blocked_events_keep_modifiers() {
+        let seen = Arc::new(Mutex::new(vec![]));
+        let mut dispatcher = Dispatcher::default();
+        dispatcher.track_modifiers(Modifiers::NUM_LOCK);
+        let log = seen.clone();
+        dispatcher.subscribe(Subscriber::Listener(Box::new(move |event: Event| {
+            log.lock().unwrap().push(event.modifiers)
+        })));
+        dispatcher.subscribe(blocker(Key::ShiftLeft));
+
+        assert_eq!(
+            dispatcher.dispatch(event(Key::ShiftLeft), false),
+            Dispatch::Block
+        );
+        assert_eq!(dispatcher.dispatch(event(Key::KeyA), false), Dispatch::Pass);
+        assert_eq!(
+            dispatcher.dispatch(event(Key::ShiftRight), false),
+            Dispatch::Pass
+        );
+        assert_eq!(dispatcher.dispatch(event(Key::KeyA), false), Dispatch::Pass);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                Modifiers::NUM_LOCK,
+                Modifiers::NUM_LOCK | Modifiers::SHIFT_RIGHT,
+                Modifiers::NUM_LOCK | Modifiers::SHIFT_RIGHT
+            ]
+        );
+    }
+
+    #[test]
+    fn test_failed_replacement_keeps_modifiers() {
+        let seen = Arc::new(Mutex::new(vec![]));
+        let mut dispatcher = Dispatcher::default();
+        let log = seen.clone();
+        dispatcher.subscribe(Subscriber::Listener(Box::new(move |event: Event| {
+            log.lock().unwrap().push(event.modifiers)
+        })));
+        dispatcher.subscribe(Subscriber::Grabber(Box::new(|event: Event| {
+            match event.event_type {
+                EventType::KeyPress(Key::ShiftLeft) => Some(Event {
+                    event_type: EventType::KeyPress(Key::KeyX),
+                    ..event
+                }),
+                _ => Some(event),
+            }
+        })));
+
+        let shift = event(Key::ShiftLeft);
+        assert!(matches!(
+            dispatcher.dispatch(shift.clone(), false),
+            Dispatch::Replace(_)
+        ));
+        // The replacement couldn't be injected, so Shift went through.
+        dispatcher.commit_original(&shift.event_type);
+        assert_eq!(dispatcher.dispatch(event(Key::KeyA), false), Dispatch::Pass);
+        assert_eq!(*seen.lock().unwrap(), vec![Modifiers::SHIFT_LEFT]);
+    }
+
+    #[test]
+    fn test_injected_events_skip_grabbers() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let mut dispatcher = Dispatcher::default();
+        dispatcher.subscribe(recorder(&log, "listener"));
+        dispatcher.subscribe(blocker(Key::Escape));
+        dispatcher.subscribe(remapper(Key::Escape, Key::CapsLock));
+
+        assert_eq!(
+            dispatcher.dispatch(event(Key::Escape), true),
+            Dispatch::Pass
+        );
+        assert_eq!(*log.lock().unwrap(), vec![("listener", Key::Escape)]);
+    }
+}
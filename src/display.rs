@@ -1,7 +1,30 @@
+use windows::Win32::Foundation::BOOL;
+use windows::Win32::Foundation::LPARAM;
+use windows::Win32::Foundation::RECT;
+use windows::Win32::Foundation::TRUE;
+use windows::Win32::Graphics::Gdi::EnumDisplayMonitors;
+use windows::Win32::Graphics::Gdi::GetMonitorInfoW;
+use windows::Win32::Graphics::Gdi::HDC;
+use windows::Win32::Graphics::Gdi::HMONITOR;
+use windows::Win32::Graphics::Gdi::MONITORINFO;
+use windows::Win32::Graphics::Gdi::MONITORINFOEXW;
+use windows::Win32::UI::HiDpi::GetDpiForMonitor;
+use windows::Win32::UI::HiDpi::MDT_EFFECTIVE_DPI;
+use windows::Win32::UI::WindowsAndMessaging::MONITORINFOF_PRIMARY;
+use windows::Win32::UI::WindowsAndMessaging::SM_CXVIRTUALSCREEN;
+use windows::Win32::UI::WindowsAndMessaging::SM_CYVIRTUALSCREEN;
+use windows::Win32::UI::WindowsAndMessaging::SM_XVIRTUALSCREEN;
+use windows::Win32::UI::WindowsAndMessaging::SM_YVIRTUALSCREEN;
 use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN};
 
 use crate::win_binder::DisplayError;
+use crate::win_binder::MonitorInfo;
+use crate::win_binder::Rect;
 use std::convert::TryInto;
+use std::mem::size_of;
+
+/// The DPI Windows scales everything from.
+const BASE_DPI: f64 = 96.0;
 
 pub fn display_size() -> Result<(u64, u64), DisplayError> {
     let w = unsafe {
@@ -17,3 +40,95 @@ pub fn display_size() -> Result<(u64, u64), DisplayError> {
 
     Ok((w, h))
 }
+
+/// The rectangle holding every monitor. Its top left corner is negative when
+/// a monitor is placed left of or above the primary one.
+pub fn virtual_screen_bounds() -> Result<Rect, DisplayError> {
+    let (left, top, width, height) = unsafe {
+        (
+            GetSystemMetrics(SM_XVIRTUALSCREEN),
+            GetSystemMetrics(SM_YVIRTUALSCREEN),
+            GetSystemMetrics(SM_CXVIRTUALSCREEN),
+            GetSystemMetrics(SM_CYVIRTUALSCREEN),
+        )
+    };
+    if width <= 0 || height <= 0 {
+        return Err(DisplayError::NoDisplay);
+    }
+    Ok(Rect::new(left, top, left + width, top + height))
+}
+
+/// Every monitor of the desktop, in the order Windows enumerates them.
+///
+/// ```no_run
+/// use win_binder::monitors;
+///
+/// for monitor in monitors().unwrap() {
+///     println!("{} {:?} x{}", monitor.device_name, monitor.bounds, monitor.scale);
+/// }
+/// ```
+pub fn monitors() -> Result<Vec<MonitorInfo>, DisplayError> {
+    let mut handles: Vec<HMONITOR> = vec![];
+    let status = unsafe {
+        EnumDisplayMonitors(
+            HDC::default(),
+            None,
+            Some(collect_monitor),
+            LPARAM(&mut handles as *mut Vec<HMONITOR> as isize),
+        )
+    };
+    if !status.as_bool() {
+        return Err(DisplayError::NoDisplay);
+    }
+    let monitors: Vec<_> = handles
+        .into_iter()
+        .filter_map(|handle| unsafe { monitor_info(handle) })
+        .collect();
+    if monitors.is_empty() {
+        return Err(DisplayError::NoDisplay);
+    }
+    Ok(monitors)
+}
+
+unsafe extern "system" fn collect_monitor(
+    handle: HMONITOR,
+    _hdc: HDC,
+    _rect: *mut RECT,
+    data: LPARAM,
+) -> BOOL {
+    let handles = &mut *(data.0 as *mut Vec<HMONITOR>);
+    handles.push(handle);
+    TRUE
+}
+
+unsafe fn monitor_info(handle: HMONITOR) -> Option<MonitorInfo> {
+    let mut info = MONITORINFOEXW::default();
+    info.monitorInfo.cbSize = size_of::<MONITORINFOEXW>() as u32;
+    if !GetMonitorInfoW(handle, &mut info as *mut MONITORINFOEXW as *mut MONITORINFO).as_bool() {
+        return None;
+    }
+    let name_len = info
+        .szDevice
+        .iter()
+        .position(|c| *c == 0)
+        .unwrap_or(info.szDevice.len());
+
+    let (mut dpi_x, mut dpi_y) = (0, 0);
+    // Assume no scaling if the DPI is not available.
+    let scale = match GetDpiForMonitor(handle, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) {
+        Ok(()) if dpi_x > 0 => dpi_x as f64 / BASE_DPI,
+        _ => 1.0,
+    };
+
+    Some(MonitorInfo {
+        bounds: rect(info.monitorInfo.rcMonitor),
+        work_area: rect(info.monitorInfo.rcWork),
+        primary: info.monitorInfo.dwFlags & MONITORINFOF_PRIMARY != 0,
+        device_name: String::from_utf16_lossy(&info.szDevice[..name_len]),
+        scale,
+    })
+}
+
+fn rect(rect: RECT) -> Rect {
+    Rect::new(rect.left, rect.top, rect.right, rect.bottom)
+}
@@ -0,0 +1,149 @@
+use crate::win_binder::MonitorInfo;
+use crate::win_binder::Rect;
+
+impl Rect {
+    pub const fn new(left: i32, top: i32, right: i32, bottom: i32) -> Rect {
+        Rect {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> i32 {
+        self.bottom - self.top
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width() <= 0 || self.height() <= 0
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        (self.left..self.right).contains(&x) && (self.top..self.bottom).contains(&y)
+    }
+
+    /// The closest point of the rectangle, the point itself if it is inside.
+    /// An empty rectangle clamps to its top left corner.
+    pub fn clamp(&self, x: i32, y: i32) -> (i32, i32) {
+        (
+            x.clamp(self.left, self.right.saturating_sub(1).max(self.left)),
+            y.clamp(self.top, self.bottom.saturating_sub(1).max(self.top)),
+        )
+    }
+
+    /// The smallest rectangle holding both.
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect::new(
+            self.left.min(other.left),
+            self.top.min(other.top),
+            self.right.max(other.right),
+            self.bottom.max(other.bottom),
+        )
+    }
+
+    fn distance_squared(&self, x: i32, y: i32) -> i128 {
+        let (cx, cy) = self.clamp(x, y);
+        let dx = i128::from(x) - i128::from(cx);
+        let dy = i128::from(y) - i128::from(cy);
+        dx * dx + dy * dy
+    }
+}
+
+/// The monitor showing a point, if any.
+pub fn monitor_at(monitors: &[MonitorInfo], x: i32, y: i32) -> Option<&MonitorInfo> {
+    monitors
+        .iter()
+        .find(|monitor| monitor.bounds.contains(x, y))
+}
+
+/// The monitor showing a point, or the closest one for the points between or
+/// outside the monitors, like `MONITOR_DEFAULTTONEAREST`.
+pub fn nearest_monitor(monitors: &[MonitorInfo], x: i32, y: i32) -> Option<&MonitorInfo> {
+    monitors
+        .iter()
+        .min_by_key(|monitor| monitor.bounds.distance_squared(x, y))
+}
+
+/// Moves a point onto the closest monitor, so that it doesn't end up in a
+/// gap between monitors of different sizes. `None` without monitors.
+pub fn clamp_to_monitors(monitors: &[MonitorInfo], x: i32, y: i32) -> Option<(i32, i32)> {
+    nearest_monitor(monitors, x, y).map(|monitor| monitor.bounds.clamp(x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(bounds: Rect, primary: bool) -> MonitorInfo {
+        MonitorInfo {
+            bounds,
+            work_area: bounds,
+            primary,
+            device_name: String::new(),
+            scale: 1.0,
+        }
+    }
+
+    /// A 1920x1080 primary monitor, with a 1280x1024 one on its left, lower.
+    fn setup() -> Vec<MonitorInfo> {
+        vec![
+            monitor(Rect::new(0, 0, 1920, 1080), true),
+            monitor(Rect::new(-1280, 200, 0, 1224), false),
+        ]
+    }
+
+    #[test]
+    fn test_rect() {
+        let rect = Rect::new(-1280, 200, 0, 1224);
+        assert_eq!((rect.width(), rect.height()), (1280, 1024));
+        assert!(rect.contains(-1280, 200));
+        assert!(!rect.contains(0, 200));
+        assert_eq!(rect.clamp(-2000, 100), (-1280, 200));
+        assert_eq!(rect.clamp(10, 2000), (-1, 1223));
+        assert_eq!(rect.clamp(-5, 500), (-5, 500));
+        assert_eq!(
+            rect.union(&Rect::new(0, 0, 1920, 1080)),
+            Rect::new(-1280, 0, 1920, 1224)
+        );
+        assert!(Rect::new(5, 5, 5, 10).is_empty());
+        assert_eq!(Rect::default().clamp(7, -7), (0, 0));
+    }
+
+    #[test]
+    fn test_monitor_at() {
+        let monitors = setup();
+        assert!(monitor_at(&monitors, 100, 100).unwrap().primary);
+        assert!(!monitor_at(&monitors, -1, 300).unwrap().primary);
+        // The gap above the left monitor.
+        assert_eq!(monitor_at(&monitors, -100, 100), None);
+        assert_eq!(monitor_at(&[], 0, 0), None);
+    }
+
+    #[test]
+    fn test_nearest_monitor() {
+        let monitors = setup();
+        assert!(!nearest_monitor(&monitors, -100, 150).unwrap().primary);
+        assert!(nearest_monitor(&monitors, -10, 50).unwrap().primary);
+        assert!(nearest_monitor(&monitors, 5000, 5000).unwrap().primary);
+        assert_eq!(
+            nearest_monitor(&monitors, i32::MIN, i32::MAX)
+                .unwrap()
+                .bounds,
+            monitors[1].bounds
+        );
+    }
+
+    #[test]
+    fn test_clamp_to_monitors() {
+        let monitors = setup();
+        assert_eq!(clamp_to_monitors(&monitors, -100, 150), Some((-100, 200)));
+        assert_eq!(clamp_to_monitors(&monitors, 3000, 500), Some((1919, 500)));
+        assert_eq!(clamp_to_monitors(&monitors, 10, 10), Some((10, 10)));
+        assert_eq!(clamp_to_monitors(&[], 10, 10), None);
+    }
+}
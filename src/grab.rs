@@ -1,77 +1,49 @@
-use windows::Win32::Foundation::HWND;
-use windows::Win32::Foundation::LPARAM;
-use windows::Win32::Foundation::LRESULT;
-use windows::Win32::Foundation::WPARAM;
-use windows::Win32::UI::WindowsAndMessaging::CallNextHookEx;
-use windows::Win32::UI::WindowsAndMessaging::GetMessageA;
-use windows::Win32::UI::WindowsAndMessaging::HC_ACTION;
-
-use crate::common::convert;
-use crate::common::set_key_hook;
-use crate::common::set_mouse_hook;
+use crate::common::message_loop;
 use crate::common::HookError;
-use crate::common::HOOK;
-use crate::common::KEYBOARD;
+use crate::dispatch::Subscriber;
+use crate::handle::ListenerHandle;
+use crate::hook::subscribe;
 use crate::win_binder::Event;
-use crate::win_binder::EventType;
 use crate::win_binder::GrabError;
-use std::os::raw::c_int;
-use std::ptr::null_mut;
-use std::time::SystemTime;
-
-static mut GLOBAL_CALLBACK: Option<Box<dyn FnMut(Event) -> Option<Event>>> = None;
 
 impl From<HookError> for GrabError {
     fn from(error: HookError) -> Self {
         match error {
             HookError::Mouse(code) => GrabError::MouseHookError(code),
             HookError::Key(code) => GrabError::KeyHookError(code),
+            HookError::Reentrant => GrabError::Reentrant,
         }
     }
 }
 
-#[allow(static_mut_refs)]
-unsafe extern "system" fn raw_callback(code: c_int, param: WPARAM, lpdata: LPARAM) -> LRESULT {
-    if (code as u32) == HC_ACTION {
-        let opt = convert(param, lpdata);
-        if let Some(event_type) = opt {
-            let name = match &event_type {
-                EventType::KeyPress(_key) => match (*KEYBOARD).lock() {
-                    Ok(mut keyboard) => keyboard.get_name(lpdata),
-                    Err(_) => None,
-                },
-                _ => None,
-            };
-            let event = Event {
-                event_type,
-                time: SystemTime::now(),
-                name,
-            };
-
-            unsafe {
-                if let Some(callback) = &mut GLOBAL_CALLBACK {
-                    if callback(event).is_none() {
-                        return LRESULT(1);
-                    }
-                }
-            }
-        }
-    }
-
-    CallNextHookEx(HOOK, code, param, lpdata)
-}
-
+/// The callback returns `None` to swallow an event, or an event to let
+/// through. Returning an event with a different `event_type` swallows the
+/// original and injects the returned one instead, so keys can be remapped.
+/// Injected events are not handed to grabbers again.
+///
+/// Blocks the current thread until WM_QUIT is posted to it (for instance with
+/// `PostQuitMessage` from inside the callback), then unhooks and returns.
+/// Use `spawn_grab` to get a handle that can stop the grabber.
 pub fn grab<T>(callback: T) -> Result<(), GrabError>
 where
     T: FnMut(Event) -> Option<Event> + 'static,
 {
+    let subscription = subscribe(Subscriber::Grabber(Box::new(callback)))?;
     unsafe {
-        GLOBAL_CALLBACK = Some(Box::new(callback));
-        set_key_hook(raw_callback)?;
-        set_mouse_hook(raw_callback)?;
-
-        let _ = GetMessageA(null_mut(), HWND(null_mut()), 0, 0);
+        message_loop();
     }
+    drop(subscription);
 
     Ok(())
 }
+
+/// Same as `grab`, but runs on a background thread owned by the returned
+/// handle. Returns once the hooks are installed.
+pub fn spawn_grab<T>(callback: T) -> Result<ListenerHandle, GrabError>
+where
+    T: FnMut(Event) -> Option<Event> + Send + 'static,
+{
+    ListenerHandle::spawn(move || {
+        subscribe(Subscriber::Grabber(Box::new(callback))).map_err(GrabError::from)
+    })
+}
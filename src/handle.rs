@@ -0,0 +1,89 @@
+use windows::Win32::Foundation::LPARAM;
+use windows::Win32::Foundation::WPARAM;
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::WindowsAndMessaging::PostThreadMessageA;
+use windows::Win32::UI::WindowsAndMessaging::WM_QUIT;
+
+use crate::common::ensure_message_queue;
+use crate::common::message_loop;
+use crate::hook::Subscription;
+use std::panic;
+use std::sync::mpsc::sync_channel;
+use std::thread;
+use std::thread::JoinHandle;
+
+/// Owns a listener (or grabber) running on a background thread, as returned by
+/// `spawn_listen` and `spawn_grab`. Calling `stop` or dropping the handle
+/// unhooks the keyboard and mouse hooks and waits for the thread to end.
+///
+/// The handle is `Send`, so it can be moved to and stopped from another thread.
+#[derive(Debug)]
+pub struct ListenerHandle {
+    thread_id: u32,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ListenerHandle {
+    /// Runs `subscribe` on a new thread and pumps that thread's messages until
+    /// the handle is stopped. Returns once the hooks are installed, or with
+    /// the error `subscribe` failed with.
+    pub(crate) fn spawn<E, F>(subscribe: F) -> Result<ListenerHandle, E>
+    where
+        E: Send + 'static,
+        F: FnOnce() -> Result<Subscription, E> + Send + 'static,
+    {
+        let (sender, receiver) = sync_channel(1);
+        let thread = thread::spawn(move || unsafe {
+            let subscription = match subscribe() {
+                Ok(subscription) => subscription,
+                Err(error) => {
+                    let _ = sender.send(Err(error));
+                    return;
+                }
+            };
+            ensure_message_queue();
+            let _ = sender.send(Ok(GetCurrentThreadId()));
+            message_loop();
+            drop(subscription);
+        });
+
+        match receiver.recv() {
+            Ok(Ok(thread_id)) => Ok(ListenerHandle {
+                thread_id,
+                thread: Some(thread),
+            }),
+            Ok(Err(error)) => {
+                let _ = thread.join();
+                Err(error)
+            }
+            // The sender was dropped without a message: `subscribe` panicked.
+            Err(_) => match thread.join() {
+                Err(payload) => panic::resume_unwind(payload),
+                Ok(()) => unreachable!("listener thread ended without reporting"),
+            },
+        }
+    }
+
+    /// Unhooks and waits for the listener thread to end. Calling it again
+    /// does nothing.
+    pub fn stop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            unsafe {
+                let _ = PostThreadMessageA(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+            }
+            let _ = thread.join();
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_some_and(|thread| !thread.is_finished())
+    }
+}
+
+impl Drop for ListenerHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
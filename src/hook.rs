@@ -0,0 +1,151 @@
+use windows::Win32::Foundation::LPARAM;
+use windows::Win32::Foundation::LRESULT;
+use windows::Win32::Foundation::WPARAM;
+use windows::Win32::UI::WindowsAndMessaging::CallNextHookEx;
+use windows::Win32::UI::WindowsAndMessaging::HC_ACTION;
+use windows::Win32::UI::WindowsAndMessaging::HHOOK;
+
+use crate::common::convert;
+use crate::common::current_modifiers;
+use crate::common::get_raw_info;
+use crate::common::HookError;
+use crate::common::Hooks;
+use crate::common::KEYBOARD;
+use crate::dispatch::Dispatch;
+use crate::dispatch::Dispatcher;
+use crate::dispatch::Subscriber;
+use crate::dispatch::SubscriberId;
+use crate::modifiers::Modifiers;
+use crate::simulate::injection_signature;
+use crate::simulate::simulate_injected;
+use crate::win_binder::Event;
+use crate::win_binder::EventOrigin;
+use crate::win_binder::EventType;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::os::raw::c_int;
+use std::ptr::null_mut;
+use std::time::SystemTime;
+
+/// Low level hooks are called on the thread that installed them, so each
+/// thread keeps its own hooks and subscribers. The hooks are installed with
+/// the first subscriber and removed with the last one.
+#[derive(Default)]
+struct HookRegistry {
+    hooks: Option<Hooks>,
+    dispatcher: Dispatcher,
+}
+
+impl HookRegistry {
+    fn unsubscribe(&mut self, id: SubscriberId) {
+        self.dispatcher.unsubscribe(id);
+        if self.dispatcher.is_empty() {
+            self.hooks = None;
+        }
+    }
+}
+
+thread_local! {
+    static REGISTRY: RefCell<HookRegistry> = RefCell::new(HookRegistry::default());
+    /// Subscriptions dropped from inside a subscriber, while the registry is
+    /// busy dispatching. They are removed once the dispatch is over.
+    static DROPPED: RefCell<Vec<SubscriberId>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Keeps a subscriber registered on the current thread until dropped.
+pub struct Subscription {
+    id: SubscriberId,
+    // Must be dropped on the thread it was created on.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        REGISTRY.with(|registry| match registry.try_borrow_mut() {
+            Ok(mut registry) => registry.unsubscribe(self.id),
+            Err(_) => DROPPED.with(|dropped| dropped.borrow_mut().push(self.id)),
+        });
+    }
+}
+
+/// Registers `subscriber` for the events of the current thread, installing
+/// the keyboard and mouse hooks if this is the first one. The thread must
+/// then pump messages (see `message_loop`) for the subscriber to be called.
+///
+/// Fails with `HookError::Reentrant` when called from inside a
+/// subscriber, while the registry is busy dispatching. Subscribing again
+/// outside of one shares the installed hooks.
+pub fn subscribe(subscriber: Subscriber) -> Result<Subscription, HookError> {
+    REGISTRY.with(|registry| {
+        let mut registry = registry
+            .try_borrow_mut()
+            .map_err(|_| HookError::Reentrant)?;
+        if registry.hooks.is_none() {
+            registry.hooks = Some(unsafe { Hooks::install(raw_callback)? });
+            registry
+                .dispatcher
+                .track_modifiers(unsafe { current_modifiers() });
+        }
+        let id = registry.dispatcher.subscribe(subscriber);
+        Ok(Subscription {
+            id,
+            _not_send: PhantomData,
+        })
+    })
+}
+
+unsafe extern "system" fn raw_callback(code: c_int, param: WPARAM, lpdata: LPARAM) -> LRESULT {
+    if (code as u32) == HC_ACTION {
+        let opt = convert(param, lpdata);
+        if let Some(event_type) = opt {
+            let name = match &event_type {
+                EventType::KeyPress(_key) => match (*KEYBOARD).lock() {
+                    Ok(mut keyboard) => keyboard.get_name(lpdata),
+                    Err(_) => None,
+                },
+                _ => None,
+            };
+            let raw = get_raw_info(param, lpdata, injection_signature());
+            let time = SystemTime::now();
+
+            let injected = raw.origin == EventOrigin::Replacement;
+            let dispatch = REGISTRY.with(|registry| match registry.try_borrow_mut() {
+                Ok(mut registry) => {
+                    let event = Event {
+                        event_type,
+                        time,
+                        name,
+                        // Set by the dispatcher.
+                        modifiers: Modifiers::empty(),
+                        raw: Some(raw),
+                    };
+                    let dispatch = registry.dispatcher.dispatch(event, injected);
+                    for id in DROPPED.with(|dropped| dropped.take()) {
+                        registry.unsubscribe(id);
+                    }
+                    dispatch
+                }
+                Err(_) => Dispatch::Pass,
+            });
+            match dispatch {
+                Dispatch::Pass => {}
+                Dispatch::Block => return LRESULT(1),
+                // If the replacement can't be injected, let the original through
+                // rather than losing the input.
+                Dispatch::Replace(event) => {
+                    if simulate_injected(&event.event_type).is_ok() {
+                        return LRESULT(1);
+                    }
+                    REGISTRY.with(|registry| {
+                        if let Ok(mut registry) = registry.try_borrow_mut() {
+                            registry.dispatcher.commit_original(&event_type);
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    // The hook handle is ignored by CallNextHookEx.
+    CallNextHookEx(HHOOK(null_mut()), code, param, lpdata)
+}
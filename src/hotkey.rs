@@ -0,0 +1,527 @@
+use crate::modifiers::Modifiers;
+use crate::win_binder::Event;
+use crate::win_binder::EventType;
+use crate::win_binder::Key;
+use crate::win_binder::ParseHotkeyError;
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Modifier groups a `Hotkey` can require. Unlike in `Modifiers`, left and
+/// right keys are not told apart, so `Ctrl` is satisfied by either
+/// `ControlLeft` or `ControlRight`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HotkeyModifier {
+    Ctrl,
+    Shift,
+    Alt,
+    Meta,
+}
+
+impl HotkeyModifier {
+    const ALL: [HotkeyModifier; 4] = [
+        HotkeyModifier::Ctrl,
+        HotkeyModifier::Shift,
+        HotkeyModifier::Alt,
+        HotkeyModifier::Meta,
+    ];
+
+    fn bit(self) -> u8 {
+        match self {
+            HotkeyModifier::Ctrl => 1 << 0,
+            HotkeyModifier::Shift => 1 << 1,
+            HotkeyModifier::Alt => 1 << 2,
+            HotkeyModifier::Meta => 1 << 3,
+        }
+    }
+
+    /// The groups a key holds. AltGr holds Ctrl+Alt, as in `Modifiers` and
+    /// as layouts see it.
+    pub(crate) fn of_key(key: Key) -> &'static [HotkeyModifier] {
+        match key {
+            Key::ControlLeft | Key::ControlRight => &[HotkeyModifier::Ctrl],
+            Key::ShiftLeft | Key::ShiftRight => &[HotkeyModifier::Shift],
+            Key::Alt => &[HotkeyModifier::Alt],
+            Key::AltGr => &[HotkeyModifier::Ctrl, HotkeyModifier::Alt],
+            Key::MetaLeft | Key::MetaRight => &[HotkeyModifier::Meta],
+            _ => &[],
+        }
+    }
+
+    /// The groups of `modifiers`, leaving out the one of `pressed`'s own
+    /// side: pressing the right Ctrl while the left one is held keeps Ctrl.
+    fn bits(modifiers: Modifiers, pressed: Key) -> u8 {
+        let modifiers = modifiers - Modifiers::from_key(pressed);
+        let mut bits = 0;
+        if modifiers.ctrl() {
+            bits |= HotkeyModifier::Ctrl.bit();
+        }
+        if modifiers.shift() {
+            bits |= HotkeyModifier::Shift.bit();
+        }
+        if modifiers.alt() {
+            bits |= HotkeyModifier::Alt.bit();
+        }
+        if modifiers.meta() {
+            bits |= HotkeyModifier::Meta.bit();
+        }
+        bits
+    }
+
+    /// The key pressed when a modifier has to be simulated.
+    pub(crate) fn key(self) -> Key {
+        match self {
+            HotkeyModifier::Ctrl => Key::ControlLeft,
+            HotkeyModifier::Shift => Key::ShiftLeft,
+            HotkeyModifier::Alt => Key::Alt,
+            HotkeyModifier::Meta => Key::MetaLeft,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<HotkeyModifier> {
+        match name.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => Some(HotkeyModifier::Ctrl),
+            "shift" => Some(HotkeyModifier::Shift),
+            "alt" | "option" => Some(HotkeyModifier::Alt),
+            "meta" | "win" | "windows" | "super" | "cmd" | "command" => Some(HotkeyModifier::Meta),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            HotkeyModifier::Ctrl => "Ctrl",
+            HotkeyModifier::Shift => "Shift",
+            HotkeyModifier::Alt => "Alt",
+            HotkeyModifier::Meta => "Meta",
+        }
+    }
+}
+
+/// A key chord such as `Ctrl+Shift+K`: a set of modifiers that must be held
+/// (and no others) when `key` is pressed.
+///
+/// ```
+/// use win_binder::{Hotkey, HotkeyModifier, Key};
+///
+/// let hotkey: Hotkey = "Ctrl+Shift+K".parse().unwrap();
+/// assert_eq!(hotkey, Hotkey::new(&[HotkeyModifier::Ctrl, HotkeyModifier::Shift], Key::KeyK));
+/// assert_eq!(hotkey.to_string(), "Ctrl+Shift+K");
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Hotkey {
+    modifiers: u8,
+    key: Key,
+}
+
+impl Hotkey {
+    pub fn new(modifiers: &[HotkeyModifier], key: Key) -> Hotkey {
+        Hotkey {
+            modifiers: modifiers.iter().fold(0, |bits, m| bits | m.bit()),
+            key,
+        }
+    }
+
+    pub fn key(&self) -> Key {
+        self.key
+    }
+
+    pub fn has(&self, modifier: HotkeyModifier) -> bool {
+        self.modifiers & modifier.bit() != 0
+    }
+
+    pub fn modifiers(&self) -> Vec<HotkeyModifier> {
+        HotkeyModifier::ALL
+            .into_iter()
+            .filter(|m| self.has(*m))
+            .collect()
+    }
+}
+
+impl FromStr for Hotkey {
+    type Err = ParseHotkeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        // "Ctrl++" means Ctrl and the key on which "+" is printed.
+        if s.trim_end().ends_with("++") {
+            parts.truncate(parts.len() - 2);
+            parts.push("=");
+        }
+        let (key_name, modifier_names) = match parts.split_last() {
+            Some((key, modifiers)) if !key.is_empty() => (*key, modifiers),
+            _ => return Err(ParseHotkeyError::MissingKey),
+        };
+
+        let mut modifiers = 0;
+        for name in modifier_names {
+            let modifier = HotkeyModifier::from_name(name)
+                .ok_or_else(|| ParseHotkeyError::UnknownModifier(name.to_string()))?;
+            if modifiers & modifier.bit() != 0 {
+                return Err(ParseHotkeyError::DuplicateModifier(modifier));
+            }
+            modifiers |= modifier.bit();
+        }
+
+        let key = key_name.parse().map_err(ParseHotkeyError::UnknownKey)?;
+        Ok(Hotkey { modifiers, key })
+    }
+}
+
+impl Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for modifier in self.modifiers() {
+            write!(f, "{}+", modifier.name())?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// Identifies a binding inside a `HotkeyRegistry`, to unregister it later.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct HotkeyId(usize);
+
+struct Binding {
+    id: HotkeyId,
+    hotkey: Hotkey,
+    callback: Box<dyn FnMut() + Send>,
+    active: bool,
+}
+
+/// Matches `Hotkey`s against a stream of events and calls the registered
+/// closures. Each binding fires once per activation: holding the key (and
+/// receiving auto-repeat presses) does not fire it again until the key is
+/// released.
+///
+/// ```no_run
+/// use win_binder::{listen, HotkeyRegistry};
+///
+/// let mut hotkeys = HotkeyRegistry::new();
+/// hotkeys.register("Ctrl+Shift+K".parse().unwrap(), || println!("Ctrl+Shift+K"));
+/// listen(move |event| {
+///     hotkeys.handle(&event);
+/// })
+/// .unwrap();
+/// ```
+#[derive(Default)]
+pub struct HotkeyRegistry {
+    bindings: Vec<Binding>,
+    held: HashSet<Key>,
+    next_id: usize,
+}
+
+impl HotkeyRegistry {
+    pub fn new() -> HotkeyRegistry {
+        HotkeyRegistry::default()
+    }
+
+    pub fn register<F>(&mut self, hotkey: Hotkey, callback: F) -> HotkeyId
+    where
+        F: FnMut() + Send + 'static,
+    {
+        let id = HotkeyId(self.next_id);
+        self.next_id += 1;
+        self.bindings.push(Binding {
+            id,
+            hotkey,
+            callback: Box::new(callback),
+            active: false,
+        });
+        id
+    }
+
+    /// Returns false if `id` was not registered.
+    pub fn unregister(&mut self, id: HotkeyId) -> bool {
+        let len = self.bindings.len();
+        self.bindings.retain(|binding| binding.id != id);
+        self.bindings.len() != len
+    }
+
+    /// Feeds an event to the registry. Returns true if at least one hotkey
+    /// fired, which `grab` callbacks can use to swallow the event.
+    ///
+    /// The modifiers of the event count as held too, so a modifier pressed
+    /// before the listener started is taken into account.
+    pub fn handle(&mut self, event: &Event) -> bool {
+        self.handle_with(&event.event_type, event.modifiers)
+    }
+
+    pub fn handle_event_type(&mut self, event_type: &EventType) -> bool {
+        self.handle_with(event_type, Modifiers::empty())
+    }
+
+    fn handle_with(&mut self, event_type: &EventType, held: Modifiers) -> bool {
+        match event_type {
+            EventType::KeyPress(key) => {
+                if !self.held.insert(*key) {
+                    // Auto-repeat.
+                    return false;
+                }
+                let modifiers = self.held_modifiers(*key) | HotkeyModifier::bits(held, *key);
+                let mut fired = false;
+                for binding in &mut self.bindings {
+                    if !binding.active
+                        && binding.hotkey.key == *key
+                        && binding.hotkey.modifiers == modifiers
+                    {
+                        binding.active = true;
+                        (binding.callback)();
+                        fired = true;
+                    }
+                }
+                fired
+            }
+            EventType::KeyRelease(key) => {
+                self.held.remove(key);
+                for binding in &mut self.bindings {
+                    if binding.hotkey.key == *key {
+                        binding.active = false;
+                    }
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Forgets every held key, for instance after the listener was restarted.
+    pub fn reset(&mut self) {
+        self.held.clear();
+        for binding in &mut self.bindings {
+            binding.active = false;
+        }
+    }
+
+    fn held_modifiers(&self, pressed: Key) -> u8 {
+        self.held
+            .iter()
+            .filter(|key| **key != pressed)
+            .flat_map(|key| HotkeyModifier::of_key(*key))
+            .fold(0, |bits, m| bits | m.bit())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    fn counter(registry: &mut HotkeyRegistry, hotkey: &str) -> Arc<AtomicUsize> {
+        let count = Arc::new(AtomicUsize::new(0));
+        let inner = count.clone();
+        registry.register(hotkey.parse().unwrap(), move || {
+            inner.fetch_add(1, Ordering::SeqCst);
+        });
+        count
+    }
+
+    fn feed(registry: &mut HotkeyRegistry, events: &[EventType]) {
+        for event in events {
+            registry.handle_event_type(event);
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            "Ctrl+Shift+K".parse::<Hotkey>().unwrap(),
+            Hotkey::new(&[HotkeyModifier::Ctrl, HotkeyModifier::Shift], Key::KeyK)
+        );
+        assert_eq!(
+            "meta + alt + f12".parse::<Hotkey>().unwrap(),
+            Hotkey::new(&[HotkeyModifier::Alt, HotkeyModifier::Meta], Key::F12)
+        );
+        assert_eq!(
+            "Ctrl++".parse::<Hotkey>().unwrap(),
+            Hotkey::new(&[HotkeyModifier::Ctrl], Key::Equal)
+        );
+        assert_eq!(
+            "Esc".parse::<Hotkey>().unwrap(),
+            Hotkey::new(&[], Key::Escape)
+        );
+        assert!(matches!(
+            "Ctrl+".parse::<Hotkey>(),
+            Err(ParseHotkeyError::MissingKey)
+        ));
+        assert!(matches!(
+            "Hyper+K".parse::<Hotkey>(),
+            Err(ParseHotkeyError::UnknownModifier(_))
+        ));
+        assert!(matches!(
+            "Ctrl+F25".parse::<Hotkey>(),
+            Err(ParseHotkeyError::UnknownKey(_))
+        ));
+        assert!(matches!(
+            "Ctrl+Control+K".parse::<Hotkey>(),
+            Err(ParseHotkeyError::DuplicateModifier(HotkeyModifier::Ctrl))
+        ));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for s in [
+            "Ctrl+Shift+K",
+            "Alt+Meta+F12",
+            "Ctrl+7",
+            "Shift+Enter",
+            "Ctrl+Alt+Delete",
+        ] {
+            let hotkey: Hotkey = s.parse().unwrap();
+            assert_eq!(hotkey.to_string(), s);
+            assert_eq!(hotkey.to_string().parse::<Hotkey>().unwrap(), hotkey);
+        }
+        let hotkey: Hotkey = "Meta+Alt+F12".parse().unwrap();
+        assert_eq!(hotkey.to_string(), "Alt+Meta+F12");
+    }
+
+    #[test]
+    fn test_fires_once_per_activation() {
+        let mut registry = HotkeyRegistry::new();
+        let count = counter(&mut registry, "Ctrl+Shift+K");
+        feed(
+            &mut registry,
+            &[
+                EventType::KeyPress(Key::ControlLeft),
+                EventType::KeyPress(Key::ShiftRight),
+                EventType::KeyPress(Key::KeyK),
+                EventType::KeyPress(Key::KeyK),
+                EventType::KeyPress(Key::KeyK),
+            ],
+        );
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        feed(
+            &mut registry,
+            &[
+                EventType::KeyRelease(Key::KeyK),
+                EventType::KeyPress(Key::KeyK),
+                EventType::KeyRelease(Key::KeyK),
+            ],
+        );
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_requires_exact_modifiers() {
+        let mut registry = HotkeyRegistry::new();
+        let count = counter(&mut registry, "Ctrl+K");
+        feed(
+            &mut registry,
+            &[
+                EventType::KeyPress(Key::KeyK),
+                EventType::KeyRelease(Key::KeyK),
+                EventType::KeyPress(Key::ControlRight),
+                EventType::KeyPress(Key::Alt),
+                EventType::KeyPress(Key::KeyK),
+                EventType::KeyRelease(Key::KeyK),
+                EventType::KeyRelease(Key::Alt),
+            ],
+        );
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+
+        assert!(registry.handle_event_type(&EventType::KeyPress(Key::KeyK)));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_modifier_pressed_after_key() {
+        let mut registry = HotkeyRegistry::new();
+        let count = counter(&mut registry, "Ctrl+K");
+        feed(
+            &mut registry,
+            &[
+                EventType::KeyPress(Key::KeyK),
+                EventType::KeyPress(Key::ControlLeft),
+                EventType::KeyPress(Key::KeyK),
+            ],
+        );
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_modifier_held_before_listening() {
+        let mut registry = HotkeyRegistry::new();
+        let count = counter(&mut registry, "Ctrl+K");
+        let event = |event_type, modifiers| Event {
+            time: std::time::SystemTime::now(),
+            name: None,
+            event_type,
+            modifiers,
+            raw: None,
+        };
+        // The press of ControlRight happened before the listener started.
+        assert!(registry.handle(&event(
+            EventType::KeyPress(Key::KeyK),
+            Modifiers::CONTROL_RIGHT
+        )));
+        registry.handle(&event(
+            EventType::KeyRelease(Key::KeyK),
+            Modifiers::CONTROL_RIGHT,
+        ));
+        // Releasing the right Ctrl doesn't make the left one count as held.
+        assert!(!registry.handle(&event(
+            EventType::KeyPress(Key::ControlLeft),
+            Modifiers::CONTROL_LEFT | Modifiers::CONTROL_RIGHT
+        )));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            HotkeyModifier::bits(Modifiers::CONTROL_LEFT, Key::ControlLeft),
+            0
+        );
+        assert_eq!(
+            HotkeyModifier::bits(Modifiers::CONTROL, Key::ControlLeft),
+            HotkeyModifier::Ctrl.bit()
+        );
+    }
+
+    #[test]
+    fn test_alt_gr_is_ctrl_alt() {
+        let mut registry = HotkeyRegistry::new();
+        let ctrl_alt = counter(&mut registry, "Ctrl+Alt+E");
+        let alt = counter(&mut registry, "Alt+E");
+        feed(
+            &mut registry,
+            &[
+                EventType::KeyPress(Key::AltGr),
+                EventType::KeyPress(Key::KeyE),
+                EventType::KeyRelease(Key::KeyE),
+                EventType::KeyRelease(Key::AltGr),
+            ],
+        );
+        registry.reset();
+        // AltGr held before listening, as `Event::modifiers` reports it.
+        registry.handle(&Event {
+            time: std::time::SystemTime::now(),
+            name: None,
+            event_type: EventType::KeyPress(Key::KeyE),
+            modifiers: Modifiers::ALT_GR | Modifiers::CONTROL_LEFT,
+            raw: None,
+        });
+        assert_eq!(ctrl_alt.load(Ordering::SeqCst), 2);
+        assert_eq!(alt.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_unregister_and_reset() {
+        let mut registry = HotkeyRegistry::new();
+        let count = Arc::new(AtomicUsize::new(0));
+        let inner = count.clone();
+        let id = registry.register("F5".parse().unwrap(), move || {
+            inner.fetch_add(1, Ordering::SeqCst);
+        });
+
+        registry.handle_event_type(&EventType::KeyPress(Key::F5));
+        registry.reset();
+        registry.handle_event_type(&EventType::KeyPress(Key::F5));
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+
+        assert!(registry.unregister(id));
+        assert!(!registry.unregister(id));
+        registry.reset();
+        assert!(!registry.handle_event_type(&EventType::KeyPress(Key::F5)));
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+}
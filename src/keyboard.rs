@@ -1,4 +1,8 @@
+use std::collections::HashMap;
 use std::ptr::null_mut;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::thread;
 
 use windows::Win32::Foundation::BOOL;
 use windows::Win32::Foundation::FALSE;
@@ -10,10 +14,22 @@ use windows::Win32::UI::Input::KeyboardAndMouse::GetKeyState;
 use windows::Win32::UI::Input::KeyboardAndMouse::GetKeyboardLayout;
 use windows::Win32::UI::Input::KeyboardAndMouse::GetKeyboardState;
 use windows::Win32::UI::Input::KeyboardAndMouse::ToUnicodeEx;
+use windows::Win32::UI::Input::KeyboardAndMouse::VkKeyScanExW;
 use windows::Win32::UI::Input::KeyboardAndMouse::HKL;
+use windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY;
 use windows::Win32::UI::Input::KeyboardAndMouse::VK_CAPITAL;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_CONTROL;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_LCONTROL;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_LMENU;
 use windows::Win32::UI::Input::KeyboardAndMouse::VK_LSHIFT;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_LWIN;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_MENU;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_NUMLOCK;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_RCONTROL;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_RMENU;
 use windows::Win32::UI::Input::KeyboardAndMouse::VK_RSHIFT;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_RWIN;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_SCROLL;
 use windows::Win32::UI::Input::KeyboardAndMouse::VK_SHIFT;
 use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
 use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
@@ -23,17 +39,127 @@ use crate::common::get_scan_code;
 use crate::common::BYTE;
 use crate::common::UINT;
 use crate::keycodes::code_from_key;
+use crate::keycodes::key_from_code;
+use crate::keystrokes::chord;
+use crate::keystrokes::modifier_keys;
+use crate::keystrokes::ALT;
+use crate::keystrokes::CONTROL;
+use crate::keystrokes::SHIFT;
+use crate::keystrokes::SHIFT_STATES;
+use crate::modifiers::Modifiers;
 use crate::win_binder::EventType;
 use crate::win_binder::Key;
 use crate::win_binder::KeyboardState;
 
 const HIGHBIT: u8 = 0x80;
+const TOGGLED: u8 = 0x01;
+/// The `ToUnicodeEx` flag that leaves the keyboard state, dead keys included,
+/// as it was.
+const NO_STATE_CHANGE: UINT = 0x4;
+
+/// A virtual key code and the `VkKeyScanEx` shift state to type it with.
+type Keystroke = (UINT, u8);
+
+/// The characters a layout only types through a dead key, with the dead key
+/// and the key typed after it.
+type DeadKeyTable = HashMap<u16, [Keystroke; 2]>;
+
+/// The dead key tables, by layout handle, built on first use.
+static DEAD_KEY_TABLES: LazyLock<Mutex<HashMap<isize, DeadKeyTable>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The entries of the keyboard state a modifier key holds down: its own and
+/// the one shared by both sides. Layouts see AltGr as Ctrl+Alt, so it also
+/// holds Control, which `Keyboard` keeps apart from the left Control key.
+fn held_codes(key: Key) -> &'static [VIRTUAL_KEY] {
+    match key {
+        Key::ShiftLeft => &[VK_SHIFT, VK_LSHIFT],
+        Key::ShiftRight => &[VK_SHIFT, VK_RSHIFT],
+        Key::ControlLeft => &[VK_CONTROL, VK_LCONTROL],
+        Key::ControlRight => &[VK_CONTROL, VK_RCONTROL],
+        Key::Alt => &[VK_MENU, VK_LMENU],
+        Key::AltGr => &[VK_MENU, VK_RMENU],
+        Key::MetaLeft => &[VK_LWIN],
+        Key::MetaRight => &[VK_RWIN],
+        _ => &[],
+    }
+}
+
+fn toggle_code(key: Key) -> Option<VIRTUAL_KEY> {
+    match key {
+        Key::CapsLock => Some(VK_CAPITAL),
+        Key::NumLock => Some(VK_NUMLOCK),
+        Key::ScrollLock => Some(VK_SCROLL),
+        _ => None,
+    }
+}
+
+/// A keyboard state with the modifiers of a `VkKeyScanEx` shift state held.
+fn shift_key_state(shift_state: u8) -> [BYTE; 256] {
+    let mut state = [0; 256];
+    for (bit, code) in [(SHIFT, VK_SHIFT), (CONTROL, VK_CONTROL), (ALT, VK_MENU)] {
+        if shift_state & bit != 0 {
+            state[code.0 as usize] = HIGHBIT;
+        }
+    }
+    state
+}
+
+/// The keystrokes of the layout for which `ToUnicodeEx` gives `len`, without
+/// changing the keyboard state.
+unsafe fn keystrokes_with_len(layout: HKL, len: i32) -> Vec<Keystroke> {
+    let mut buff = [0_u16; 32];
+    let mut keystrokes = vec![];
+    for code in 1..0xFF {
+        for shift_state in SHIFT_STATES {
+            let state = shift_key_state(shift_state);
+            if ToUnicodeEx(code, 0, &state, &mut buff, NO_STATE_CHANGE, layout) == len {
+                keystrokes.push((code, shift_state));
+            }
+        }
+    }
+    keystrokes
+}
+
+/// Types each dead key of the layout followed by each key that gives a
+/// character. Composing needs the dead key to change the keyboard state, so
+/// this runs on its own thread to leave the state of the caller alone.
+fn dead_key_table(layout: isize) -> DeadKeyTable {
+    let build = move || unsafe {
+        let layout = HKL(layout as *mut _);
+        let mut buff = [0_u16; 32];
+        let mut table = DeadKeyTable::new();
+        let characters = keystrokes_with_len(layout, 1);
+        for dead in keystrokes_with_len(layout, -1) {
+            let dead_state = shift_key_state(dead.1);
+            for &(code, shift_state) in &characters {
+                ToUnicodeEx(dead.0, 0, &dead_state, &mut buff, 0, layout);
+                let state = shift_key_state(shift_state);
+                // Keys that don't compose give two characters and end the dead key too.
+                if ToUnicodeEx(code, 0, &state, &mut buff, 0, layout) == 1 {
+                    table.entry(buff[0]).or_insert([dead, (code, shift_state)]);
+                }
+            }
+        }
+        table
+    };
+    thread::spawn(build).join().unwrap_or_default()
+}
+
+unsafe fn foreground_layout() -> HKL {
+    let current_window_thread_id =
+        GetWindowThreadProcessId(GetForegroundWindow(), Some(null_mut()));
+    GetKeyboardLayout(current_window_thread_id)
+}
 
 pub struct Keyboard {
     last_code: UINT,
     last_scan_code: UINT,
     last_state: [BYTE; 256],
     last_is_dead: bool,
+    /// The Control held by AltGr, so that releasing either AltGr or the left
+    /// Control doesn't release the Control of the other.
+    alt_gr_control: bool,
 }
 
 impl Keyboard {
@@ -43,6 +169,7 @@ impl Keyboard {
             last_scan_code: 0,
             last_state: [0; 256],
             last_is_dead: false,
+            alt_gr_control: false,
         })
     }
 
@@ -76,16 +203,137 @@ impl Keyboard {
         }
 
         self.last_state = state;
+        // The state of the OS already has the Control of AltGr.
+        self.alt_gr_control = false;
         Some(())
     }
 
+    /// The modifiers held and the lock keys toggled on, as seen by the
+    /// last `add`. AltGr shows as `ALT_GR | CONTROL_LEFT`, like Windows
+    /// reports it.
+    pub fn modifiers(&self) -> Modifiers {
+        let state = |key| code_from_key(key).map_or(0, |code| self.last_state[code as usize]);
+        Modifiers::from_state(
+            |key| state(key) & HIGHBIT != 0,
+            |key| state(key) & TOGGLED != 0,
+        )
+    }
+
+    fn is_down(&self, code: VIRTUAL_KEY) -> bool {
+        self.last_state[code.0 as usize] & HIGHBIT != 0
+    }
+
+    /// Updates the keyboard state for a modifier or lock key, returns false
+    /// for the other keys.
+    fn update_state(&mut self, event_type: &EventType) -> bool {
+        let (key, pressed) = match event_type {
+            EventType::KeyPress(key) => (*key, true),
+            EventType::KeyRelease(key) => (*key, false),
+            _ => return false,
+        };
+        if let Some(code) = toggle_code(key) {
+            let state = &mut self.last_state[code.0 as usize];
+            // Auto-repeat doesn't toggle the key again.
+            if pressed && *state & HIGHBIT == 0 {
+                *state ^= TOGGLED;
+            }
+            if pressed {
+                *state |= HIGHBIT;
+            } else {
+                *state &= !HIGHBIT;
+            }
+            return true;
+        }
+        let codes = held_codes(key);
+        if codes.is_empty() {
+            return false;
+        }
+        if key == Key::AltGr {
+            self.alt_gr_control = pressed;
+        }
+        for code in codes {
+            if pressed {
+                self.last_state[code.0 as usize] |= HIGHBIT;
+            } else {
+                self.last_state[code.0 as usize] &= !HIGHBIT;
+            }
+        }
+        // The shared entries stay down while the other side is held.
+        for (shared, left, right, implied) in [
+            (VK_SHIFT, VK_LSHIFT, VK_RSHIFT, false),
+            (VK_CONTROL, VK_LCONTROL, VK_RCONTROL, self.alt_gr_control),
+            (VK_MENU, VK_LMENU, VK_RMENU, false),
+        ] {
+            if self.is_down(left) || self.is_down(right) || implied {
+                self.last_state[shared.0 as usize] |= HIGHBIT;
+            } else {
+                self.last_state[shared.0 as usize] &= !HIGHBIT;
+            }
+        }
+        true
+    }
+
+    /// The keys to press and release, in order, to type `character` with the
+    /// layout of the foreground window, holding Shift or AltGr or going
+    /// through a dead key if needed. A dead key typed on its own is followed
+    /// by a space. `None` if the layout can't produce the character.
+    ///
+    /// Unlike `simulate_text`, the events can be sent to applications that
+    /// ignore Unicode input.
+    pub fn keystrokes_for(&self, character: char) -> Option<Vec<EventType>> {
+        let mut units = [0_u16; 2];
+        let &mut [unit] = character.encode_utf16(&mut units) else {
+            return None;
+        };
+        unsafe {
+            let layout = foreground_layout();
+            match VkKeyScanExW(unit, layout) {
+                -1 => self.dead_keystrokes_for(unit, layout),
+                scan => {
+                    let code = (scan & 0xFF) as UINT;
+                    let shift_state = (scan >> 8) as u8;
+                    let mut events =
+                        chord(&modifier_keys(shift_state)?, key_from_code(code as u16));
+                    if self.is_dead(code, shift_state, layout) {
+                        events.extend(chord(&[], Key::Space));
+                    }
+                    Some(events)
+                }
+            }
+        }
+    }
+
+    unsafe fn is_dead(&self, code: UINT, shift_state: u8, layout: HKL) -> bool {
+        let mut buff = [0_u16; 32];
+        let state = shift_key_state(shift_state);
+        ToUnicodeEx(code, 0, &state, &mut buff, NO_STATE_CHANGE, layout) < 0
+    }
+
+    /// Looks the character up in the dead key table of the layout, built
+    /// the first time the layout is used.
+    fn dead_keystrokes_for(&self, unit: u16, layout: HKL) -> Option<Vec<EventType>> {
+        let Ok(mut tables) = DEAD_KEY_TABLES.lock() else {
+            return None;
+        };
+        let layout = layout.0 as isize;
+        let table = tables
+            .entry(layout)
+            .or_insert_with(|| dead_key_table(layout));
+        let mut events = vec![];
+        for (code, shift_state) in *table.get(&unit)? {
+            events.extend(chord(
+                &modifier_keys(shift_state)?,
+                key_from_code(code as u16),
+            ));
+        }
+        Some(events)
+    }
+
     pub(crate) unsafe fn get_code_name(&mut self, code: UINT, scan_code: UINT) -> Option<String> {
-        let current_window_thread_id =
-            GetWindowThreadProcessId(GetForegroundWindow(), Some(null_mut()));
         const BUF_LEN: i32 = 32;
         let mut buff = [0_u16; BUF_LEN as usize];
 
-        let layout = GetKeyboardLayout(current_window_thread_id);
+        let layout = foreground_layout();
         let len = ToUnicodeEx(code, scan_code, &self.last_state, &mut buff, 0, layout);
 
         let mut is_dead = false;
@@ -135,47 +383,121 @@ impl Keyboard {
 
 impl KeyboardState for Keyboard {
     fn add(&mut self, event_type: &EventType) -> Option<String> {
+        if self.update_state(event_type) {
+            return None;
+        }
         match event_type {
-            EventType::KeyPress(key) => match key {
-                Key::ShiftLeft => {
-                    self.last_state[VK_SHIFT.0 as usize] |= HIGHBIT;
-                    self.last_state[VK_LSHIFT.0 as usize] |= HIGHBIT;
-                    None
-                }
-                Key::ShiftRight => {
-                    self.last_state[VK_SHIFT.0 as usize] |= HIGHBIT;
-                    self.last_state[VK_RSHIFT.0 as usize] |= HIGHBIT;
-
-                    None
-                }
-                Key::CapsLock => {
-                    self.last_state[VK_CAPITAL.0 as usize] ^= 1;
-                    None
-                }
-                key => {
-                    let code = code_from_key(*key)?;
-                    unsafe { self.get_code_name(code.into(), 0) }
-                }
-            },
-            EventType::KeyRelease(key) => match key {
-                Key::ShiftLeft => {
-                    self.last_state[VK_SHIFT.0 as usize] &= HIGHBIT;
-                    self.last_state[VK_LSHIFT.0 as usize] &= HIGHBIT;
-                    None
-                }
-                Key::ShiftRight => {
-                    self.last_state[VK_SHIFT.0 as usize] &= HIGHBIT;
-                    self.last_state[VK_RSHIFT.0 as usize] &= HIGHBIT;
-                    None
-                }
-                _ => None,
-            },
+            EventType::KeyPress(key) => {
+                let code = code_from_key(*key)?;
+                unsafe { self.get_code_name(code.into(), 0) }
+            }
             _ => None,
         }
     }
 
     fn reset(&mut self) {
-        self.last_state[16] = 0;
-        self.last_state[20] = 0;
+        self.last_state = [0; 256];
+        self.last_code = 0;
+        self.last_scan_code = 0;
+        self.last_is_dead = false;
+        self.alt_gr_control = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(keyboard: &mut Keyboard, key: Key) {
+        keyboard.add(&EventType::KeyPress(key));
+    }
+
+    fn release(keyboard: &mut Keyboard, key: Key) {
+        keyboard.add(&EventType::KeyRelease(key));
+    }
+
+    #[test]
+    fn test_modifiers() {
+        let mut keyboard = Keyboard::new().unwrap();
+        press(&mut keyboard, Key::ShiftLeft);
+        press(&mut keyboard, Key::ShiftRight);
+        press(&mut keyboard, Key::MetaRight);
+        assert_eq!(
+            keyboard.modifiers(),
+            Modifiers::SHIFT | Modifiers::META_RIGHT
+        );
+
+        // Shift stays down while the other side is held.
+        release(&mut keyboard, Key::ShiftLeft);
+        assert_eq!(
+            keyboard.modifiers(),
+            Modifiers::SHIFT_RIGHT | Modifiers::META_RIGHT
+        );
+        assert!(keyboard.is_down(VK_SHIFT));
+        release(&mut keyboard, Key::ShiftRight);
+        release(&mut keyboard, Key::MetaRight);
+        assert!(!keyboard.is_down(VK_SHIFT));
+        assert_eq!(keyboard.modifiers(), Modifiers::empty());
+    }
+
+    #[test]
+    fn test_alt_gr_is_ctrl_alt() {
+        let mut keyboard = Keyboard::new().unwrap();
+        press(&mut keyboard, Key::AltGr);
+        assert_eq!(
+            keyboard.modifiers(),
+            Modifiers::ALT_GR | Modifiers::CONTROL_LEFT
+        );
+        assert!(keyboard.is_down(VK_CONTROL));
+        assert!(keyboard.is_down(VK_MENU));
+        release(&mut keyboard, Key::AltGr);
+        assert!(!keyboard.is_down(VK_CONTROL));
+        assert!(!keyboard.is_down(VK_MENU));
+    }
+
+    #[test]
+    fn test_alt_gr_with_control_left() {
+        let mut keyboard = Keyboard::new().unwrap();
+        press(&mut keyboard, Key::ControlLeft);
+        press(&mut keyboard, Key::AltGr);
+        release(&mut keyboard, Key::AltGr);
+        assert_eq!(keyboard.modifiers(), Modifiers::CONTROL_LEFT);
+        assert!(keyboard.is_down(VK_CONTROL));
+        release(&mut keyboard, Key::ControlLeft);
+        assert!(!keyboard.is_down(VK_CONTROL));
+
+        press(&mut keyboard, Key::AltGr);
+        press(&mut keyboard, Key::ControlLeft);
+        release(&mut keyboard, Key::ControlLeft);
+        assert_eq!(
+            keyboard.modifiers(),
+            Modifiers::ALT_GR | Modifiers::CONTROL_LEFT
+        );
+        assert!(keyboard.is_down(VK_CONTROL));
+        assert!(keyboard.is_down(VK_MENU));
+        release(&mut keyboard, Key::AltGr);
+        assert_eq!(keyboard.modifiers(), Modifiers::empty());
+    }
+
+    #[test]
+    fn test_toggles() {
+        let mut keyboard = Keyboard::new().unwrap();
+        press(&mut keyboard, Key::NumLock);
+        // Auto-repeat
+        press(&mut keyboard, Key::NumLock);
+        release(&mut keyboard, Key::NumLock);
+        press(&mut keyboard, Key::ScrollLock);
+        release(&mut keyboard, Key::ScrollLock);
+        assert_eq!(
+            keyboard.modifiers(),
+            Modifiers::NUM_LOCK | Modifiers::SCROLL_LOCK
+        );
+
+        press(&mut keyboard, Key::NumLock);
+        release(&mut keyboard, Key::NumLock);
+        assert_eq!(keyboard.modifiers(), Modifiers::SCROLL_LOCK);
+
+        keyboard.reset();
+        assert_eq!(keyboard.modifiers(), Modifiers::empty());
     }
 }
@@ -2,11 +2,19 @@ use crate::common::WORD;
 use crate::win_binder::Key;
 use std::convert::TryInto;
 
+/// `VK_RETURN`, shared by `Return` and `KpReturn`.
+const RETURN: WORD = 0x0D;
+
 macro_rules! decl_keycodes {
     ($($key:ident, $code:literal),*) => {
+        /// Every key with a virtual key code of its own.
+        #[cfg(test)]
+        pub(crate) const KEYS: &[Key] = &[$(Key::$key),*];
+
         //TODO: make const when rust lang issue #49146 is fixed
         pub fn code_from_key(key: Key) -> Option<WORD> {
             match key {
+                Key::KpReturn => Some(RETURN),
                 $(
                     Key::$key => Some($code),
                 )*
@@ -115,7 +123,6 @@ decl_keycodes! {
     Dot, 190,
     Slash, 191,
     Insert, 45,
-    //KP_RETURN, 13,
     KpMinus, 109,
     KpPlus, 107,
     KpMultiply, 106,
@@ -130,5 +137,118 @@ decl_keycodes! {
     Kp7, 103,
     Kp8, 104,
     Kp9, 105,
-    KpDelete, 110
+    KpDelete, 110,
+    MetaRight, 92,
+    ContextMenu, 93,
+    Sleep, 95,
+    KpSeparator, 108,
+    F13, 124,
+    F14, 125,
+    F15, 126,
+    F16, 127,
+    F17, 128,
+    F18, 129,
+    F19, 130,
+    F20, 131,
+    F21, 132,
+    F22, 133,
+    F23, 134,
+    F24, 135,
+    Cancel, 3,
+    Clear, 12,
+    Select, 41,
+    Print, 42,
+    Execute, 43,
+    Help, 47,
+    Kana, 21,
+    ImeOn, 22,
+    Junja, 23,
+    Final, 24,
+    Kanji, 25,
+    ImeOff, 26,
+    Convert, 28,
+    NonConvert, 29,
+    Accept, 30,
+    ModeChange, 31,
+    ImeProcess, 229,
+    BrowserBack, 166,
+    BrowserForward, 167,
+    BrowserRefresh, 168,
+    BrowserStop, 169,
+    BrowserSearch, 170,
+    BrowserFavorites, 171,
+    BrowserHome, 172,
+    VolumeMute, 173,
+    VolumeDown, 174,
+    VolumeUp, 175,
+    MediaNextTrack, 176,
+    MediaPrevTrack, 177,
+    MediaStop, 178,
+    MediaPlayPause, 179,
+    LaunchMail, 180,
+    LaunchMediaSelect, 181,
+    LaunchApp1, 182,
+    LaunchApp2, 183,
+    Oem8, 223,
+    Attn, 246,
+    CrSel, 247,
+    ExSel, 248,
+    EraseEof, 249,
+    Play, 250,
+    Zoom, 251,
+    Pa1, 253,
+    OemClear, 254
+}
+
+/// The key reported by a hook, `extended` being its `LLKHF_EXTENDED` flag:
+/// the numpad Enter only differs from Enter by this flag.
+pub fn key_from_hook(code: WORD, extended: bool) -> Key {
+    match key_from_code(code) {
+        Key::Return if extended => Key::KpReturn,
+        key => key,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_keys_round_trip() {
+        for key in KEYS {
+            let code = code_from_key(*key).unwrap();
+            assert_eq!(key_from_code(code), *key, "{:?} ({})", key, code);
+        }
+    }
+
+    #[test]
+    fn test_codes_round_trip() {
+        let mut seen = HashSet::new();
+        for code in 0..=255 {
+            let key = key_from_code(code);
+            assert_eq!(code_from_key(key), Some(code), "{:?} ({})", key, code);
+            assert!(seen.insert(key), "{:?} has two codes", key);
+        }
+    }
+
+    #[test]
+    fn test_unknown_codes() {
+        // Mouse buttons, and the generic Shift, Control and Menu that hooks
+        // never report.
+        for code in [1, 2, 4, 5, 6, 16, 17, 18] {
+            assert_eq!(key_from_code(code), Key::Unknown(code.into()));
+        }
+        assert_eq!(code_from_key(Key::Unknown(0x1_0000)), None);
+        assert_eq!(code_from_key(Key::Function), None);
+    }
+
+    #[test]
+    fn test_kp_return() {
+        assert_eq!(code_from_key(Key::KpReturn), Some(RETURN));
+        assert_eq!(key_from_code(RETURN), Key::Return);
+        assert_eq!(key_from_hook(RETURN, false), Key::Return);
+        assert_eq!(key_from_hook(RETURN, true), Key::KpReturn);
+        assert_eq!(key_from_hook(45, true), Key::Insert);
+    }
 }
@@ -0,0 +1,81 @@
+use crate::win_binder::EventType;
+use crate::win_binder::Key;
+
+/// The shift state bits `VkKeyScanEx` returns in its high byte.
+pub const SHIFT: u8 = 1;
+pub const CONTROL: u8 = 2;
+pub const ALT: u8 = 4;
+
+/// The shift states tried when looking for a character through dead keys.
+pub const SHIFT_STATES: [u8; 4] = [0, SHIFT, CONTROL | ALT, SHIFT | CONTROL | ALT];
+
+/// The modifiers to hold for a shift state, in the order they are pressed.
+/// Ctrl+Alt is AltGr, which Windows turns into Ctrl+Alt on layouts that have
+/// it. `None` for the Hankaku and layout specific bits, which we can't press.
+pub fn modifier_keys(shift_state: u8) -> Option<Vec<Key>> {
+    if shift_state & !(SHIFT | CONTROL | ALT) != 0 {
+        return None;
+    }
+    let mut keys = vec![];
+    if shift_state & SHIFT != 0 {
+        keys.push(Key::ShiftLeft);
+    }
+    match shift_state & (CONTROL | ALT) {
+        CONTROL => keys.push(Key::ControlLeft),
+        ALT => keys.push(Key::Alt),
+        0 => {}
+        _ => keys.push(Key::AltGr),
+    }
+    Some(keys)
+}
+
+/// Presses the modifiers, then presses and releases `key`, then releases the
+/// modifiers in reverse order.
+pub fn chord(modifiers: &[Key], key: Key) -> Vec<EventType> {
+    let mut events: Vec<_> = modifiers.iter().copied().map(EventType::KeyPress).collect();
+    events.push(EventType::KeyPress(key));
+    events.push(EventType::KeyRelease(key));
+    events.extend(modifiers.iter().rev().copied().map(EventType::KeyRelease));
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modifier_keys() {
+        assert_eq!(modifier_keys(0), Some(vec![]));
+        assert_eq!(modifier_keys(SHIFT), Some(vec![Key::ShiftLeft]));
+        assert_eq!(modifier_keys(CONTROL), Some(vec![Key::ControlLeft]));
+        assert_eq!(modifier_keys(CONTROL | ALT), Some(vec![Key::AltGr]));
+        assert_eq!(
+            modifier_keys(SHIFT | CONTROL | ALT),
+            Some(vec![Key::ShiftLeft, Key::AltGr])
+        );
+        // Hankaku
+        assert_eq!(modifier_keys(8), None);
+    }
+
+    #[test]
+    fn test_chord() {
+        assert_eq!(
+            chord(&[Key::ShiftLeft, Key::AltGr], Key::KeyE),
+            vec![
+                EventType::KeyPress(Key::ShiftLeft),
+                EventType::KeyPress(Key::AltGr),
+                EventType::KeyPress(Key::KeyE),
+                EventType::KeyRelease(Key::KeyE),
+                EventType::KeyRelease(Key::AltGr),
+                EventType::KeyRelease(Key::ShiftLeft),
+            ]
+        );
+        assert_eq!(
+            chord(&[], Key::Space),
+            vec![
+                EventType::KeyPress(Key::Space),
+                EventType::KeyRelease(Key::Space)
+            ]
+        );
+    }
+}
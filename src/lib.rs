@@ -1,30 +1,122 @@
 #[allow(clippy::upper_case_acronyms)]
 mod common;
+mod coordinates;
+mod dispatch;
 mod display;
+mod geometry;
 #[cfg(feature = "unstable_grab")]
 mod grab;
+mod handle;
+mod hook;
+mod hotkey;
 mod keyboard;
 mod keycodes;
+mod keystrokes;
 mod listen;
+mod modifiers;
+mod names;
+mod os_error;
+mod receiver;
+#[cfg(feature = "record")]
+mod record;
+mod remap;
+mod scancodes;
+mod scroll;
 mod simulate;
+#[cfg(feature = "futures")]
+mod stream;
 mod win_binder;
 
 pub use crate::display::display_size;
+pub use crate::display::monitors;
+pub use crate::display::virtual_screen_bounds;
+pub use crate::geometry::clamp_to_monitors;
+pub use crate::geometry::monitor_at;
+pub use crate::geometry::nearest_monitor;
 #[cfg(feature = "unstable_grab")]
 pub use crate::grab::grab;
+#[cfg(feature = "unstable_grab")]
+pub use crate::grab::spawn_grab;
+pub use crate::handle::ListenerHandle;
+pub use crate::hotkey::Hotkey;
+pub use crate::hotkey::HotkeyId;
+pub use crate::hotkey::HotkeyModifier;
+pub use crate::hotkey::HotkeyRegistry;
 pub use crate::keyboard::Keyboard;
 pub use crate::listen::listen;
+pub use crate::listen::listen_with;
+pub use crate::listen::spawn_listen;
+pub use crate::listen::spawn_listen_with;
+pub use crate::listen::ListenOptions;
+pub use crate::modifiers::ModifierTracker;
+pub use crate::modifiers::Modifiers;
+pub use crate::receiver::events;
+pub use crate::receiver::EventReceiver;
+#[cfg(feature = "record")]
+pub use crate::record::play;
+#[cfg(feature = "record")]
+pub use crate::record::read_recording;
+#[cfg(feature = "record")]
+pub use crate::record::Player;
+#[cfg(feature = "record")]
+pub use crate::record::RecordFormat;
+#[cfg(feature = "record")]
+pub use crate::record::RecordReader;
+#[cfg(feature = "record")]
+pub use crate::record::RecordedEvent;
+#[cfg(feature = "record")]
+pub use crate::record::Recorder;
+#[cfg(feature = "record")]
+pub use crate::record::Speed;
+#[cfg(feature = "record")]
+pub use crate::record::RECORD_VERSION;
+pub use crate::remap::Keymap;
+pub use crate::remap::Remap;
+pub use crate::remap::Remapper;
+pub use crate::scroll::Scroll;
+pub use crate::scroll::ScrollAccumulator;
+pub use crate::scroll::ScrollStep;
+pub use crate::simulate::injection_signature;
+pub use crate::simulate::set_injection_signature;
 pub use crate::simulate::simulate;
+pub use crate::simulate::simulate_batch;
+pub use crate::simulate::simulate_text;
+pub use crate::simulate::simulate_with;
+pub use crate::simulate::SimulateOptions;
+#[cfg(feature = "futures")]
+pub use crate::stream::listen_stream;
+#[cfg(feature = "futures")]
+pub use crate::stream::listen_stream_with;
+#[cfg(feature = "futures")]
+pub use crate::stream::EventStream;
+#[cfg(feature = "futures")]
+pub use crate::stream::OverflowPolicy;
+#[cfg(feature = "futures")]
+pub use crate::stream::StreamOptions;
+pub use crate::win_binder::BatchError;
 pub use crate::win_binder::Button;
 pub use crate::win_binder::DisplayError;
 pub use crate::win_binder::Event;
+pub use crate::win_binder::EventOrigin;
 pub use crate::win_binder::EventType;
 pub use crate::win_binder::GrabCallback;
 pub use crate::win_binder::GrabError;
 pub use crate::win_binder::Key;
 pub use crate::win_binder::KeyboardState;
+#[cfg(feature = "config")]
+pub use crate::win_binder::KeymapError;
 pub use crate::win_binder::ListenError;
+pub use crate::win_binder::MonitorInfo;
+pub use crate::win_binder::OsError;
+pub use crate::win_binder::ParseHotkeyError;
+pub use crate::win_binder::ParseNameError;
+pub use crate::win_binder::RawEventInfo;
+#[cfg(feature = "record")]
+pub use crate::win_binder::RecordError;
+pub use crate::win_binder::Rect;
+pub use crate::win_binder::ScanCode;
 pub use crate::win_binder::SimulateError;
+pub use crate::win_binder::WheelUnit;
 
 #[cfg(test)]
 mod tests {
@@ -85,4 +177,29 @@ mod tests {
         // assert_eq!(e, "é".to_string());
         // keyboard.add(&EventType::KeyRelease(Key::KeyE));
     }
+
+    #[test]
+    fn test_keystrokes_for() {
+        let keyboard = Keyboard::new().unwrap();
+        assert_eq!(
+            keyboard.keystrokes_for('A'),
+            Some(vec![
+                EventType::KeyPress(Key::ShiftLeft),
+                EventType::KeyPress(Key::KeyA),
+                EventType::KeyRelease(Key::KeyA),
+                EventType::KeyRelease(Key::ShiftLeft),
+            ]),
+            "This test should pass only on Qwerty layout !"
+        );
+        assert_eq!(
+            keyboard.keystrokes_for('@'),
+            Some(vec![
+                EventType::KeyPress(Key::ShiftLeft),
+                EventType::KeyPress(Key::Num2),
+                EventType::KeyRelease(Key::Num2),
+                EventType::KeyRelease(Key::ShiftLeft),
+            ])
+        );
+        assert_eq!(keyboard.keystrokes_for('😀'), None);
+    }
 }
@@ -1,75 +1,119 @@
-use windows::Win32::Foundation::HWND;
-use windows::Win32::Foundation::LPARAM;
-use windows::Win32::Foundation::LRESULT;
-use windows::Win32::Foundation::WPARAM;
-use windows::Win32::UI::WindowsAndMessaging::CallNextHookEx;
-use windows::Win32::UI::WindowsAndMessaging::GetMessageA;
-use windows::Win32::UI::WindowsAndMessaging::HC_ACTION;
-
-use crate::common::convert;
-use crate::common::set_key_hook;
-use crate::common::set_mouse_hook;
+use crate::common::message_loop;
 use crate::common::HookError;
-use crate::common::HOOK;
-use crate::common::KEYBOARD;
+use crate::coordinates::MotionTracker;
+use crate::dispatch::Subscriber;
+use crate::handle::ListenerHandle;
+use crate::hook::subscribe;
 use crate::win_binder::Event;
-use crate::win_binder::EventType;
+use crate::win_binder::EventOrigin;
 use crate::win_binder::ListenError;
-use std::os::raw::c_int;
-use std::ptr::null_mut;
-use std::time::SystemTime;
-
-static mut GLOBAL_CALLBACK: Option<Box<dyn FnMut(Event)>> = None;
 
 impl From<HookError> for ListenError {
     fn from(error: HookError) -> Self {
         match error {
             HookError::Mouse(code) => ListenError::MouseHookError(code),
             HookError::Key(code) => ListenError::KeyHookError(code),
+            HookError::Reentrant => ListenError::Reentrant,
         }
     }
 }
 
-#[allow(static_mut_refs)]
-unsafe extern "system" fn raw_callback(code: c_int, param: WPARAM, lpdata: LPARAM) -> LRESULT {
-    if (code as u32) == HC_ACTION {
-        let opt = convert(param, lpdata);
-        if let Some(event_type) = opt {
-            let name = match &event_type {
-                EventType::KeyPress(_key) => match (*KEYBOARD).lock() {
-                    Ok(mut keyboard) => keyboard.get_name(lpdata),
-                    Err(_) => None,
-                },
-                _ => None,
+/// Blocks the current thread until WM_QUIT is posted to it (for instance with
+/// `PostQuitMessage` from inside the callback), then unhooks and returns.
+/// Use `spawn_listen` to get a handle that can stop the listener.
+pub fn listen<T>(callback: T) -> Result<(), ListenError>
+where
+    T: FnMut(Event) + 'static,
+{
+    let subscription = subscribe(Subscriber::Listener(Box::new(callback)))?;
+    unsafe {
+        message_loop();
+    }
+    drop(subscription);
+
+    Ok(())
+}
+
+/// Same as `listen`, but runs on a background thread owned by the returned
+/// handle. Returns once the hooks are installed.
+pub fn spawn_listen<T>(callback: T) -> Result<ListenerHandle, ListenError>
+where
+    T: FnMut(Event) + Send + 'static,
+{
+    spawn_listen_with(ListenOptions::default(), callback)
+}
+
+/// Which events `listen_with` and `spawn_listen_with` hand to the callback.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct ListenOptions {
+    /// Skip the events simulated by this process (`EventOrigin::SelfInjected`),
+    /// so that a callback simulating events doesn't receive them back.
+    pub skip_self_injected: bool,
+    /// After each `MouseMove`, also report a `MouseMoveRelative` with the
+    /// distance from the previous position. The cursor stops at the edges
+    /// of the screen, and so do these moves.
+    pub relative_moves: bool,
+}
+
+impl ListenOptions {
+    fn wrap<T>(self, mut callback: T) -> impl FnMut(Event)
+    where
+        T: FnMut(Event),
+    {
+        let mut motion = MotionTracker::default();
+        move |event: Event| {
+            // Keep following the cursor through the skipped moves.
+            let relative = match self.relative_moves {
+                true => motion.update(&event.event_type),
+                false => None,
             };
-            let event = Event {
+            if self.skip_self_injected && event.origin() == Some(EventOrigin::SelfInjected) {
+                return;
+            }
+            let relative = relative.map(|event_type| Event {
                 event_type,
-                time: SystemTime::now(),
-                name,
-            };
-
-            unsafe {
-                if let Some(ref mut callback) = &mut GLOBAL_CALLBACK {
-                    callback(event); // Call the closure
-                }
+                ..event.clone()
+            });
+            callback(event);
+            if let Some(relative) = relative {
+                callback(relative);
             }
         }
     }
-
-    CallNextHookEx(HOOK, code, param, lpdata)
 }
 
-pub fn listen<T>(callback: T) -> Result<(), ListenError>
+/// ```no_run
+/// use win_binder::{listen_with, simulate, EventType, Key, ListenOptions};
+///
+/// let options = ListenOptions {
+///     skip_self_injected: true,
+///     ..ListenOptions::default()
+/// };
+/// // Echoes every A typed, without looping on the echoed ones.
+/// listen_with(options, |event| {
+///     if event.event_type == EventType::KeyPress(Key::KeyA) {
+///         simulate(&EventType::KeyPress(Key::KeyA)).unwrap();
+///         simulate(&EventType::KeyRelease(Key::KeyA)).unwrap();
+///     }
+/// })
+/// .unwrap();
+/// ```
+pub fn listen_with<T>(options: ListenOptions, callback: T) -> Result<(), ListenError>
 where
     T: FnMut(Event) + 'static,
 {
-    unsafe {
-        GLOBAL_CALLBACK = Some(Box::new(callback));
-        set_key_hook(raw_callback)?;
-        set_mouse_hook(raw_callback)?;
-
-        let _ = GetMessageA(null_mut(), HWND(null_mut()), 0, 0);
-    }
+    listen(options.wrap(callback))
+}
 
-    Ok(())
+pub fn spawn_listen_with<T>(
+    options: ListenOptions,
+    callback: T,
+) -> Result<ListenerHandle, ListenError>
+where
+    T: FnMut(Event) + Send + 'static,
+{
+    let callback = options.wrap(callback);
+    ListenerHandle::spawn(move || {
+        subscribe(Subscriber::Listener(Box::new(callback))).map_err(ListenError::from)
+    })
 }
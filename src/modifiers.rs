@@ -0,0 +1,301 @@
+use crate::win_binder::EventType;
+use crate::win_binder::Key;
+use bitflags::bitflags;
+
+bitflags! {
+    /// The modifier keys held and the lock keys toggled on, left and right
+    /// keys being told apart.
+    ///
+    /// ```
+    /// use win_binder::Modifiers;
+    ///
+    /// let modifiers = Modifiers::CONTROL_RIGHT | Modifiers::SHIFT_LEFT;
+    /// assert!(modifiers.ctrl() && modifiers.shift() && !modifiers.alt());
+    /// assert!(modifiers.intersects(Modifiers::CONTROL));
+    /// ```
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+    #[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+    pub struct Modifiers: u16 {
+        const SHIFT_LEFT = 1 << 0;
+        const SHIFT_RIGHT = 1 << 1;
+        const CONTROL_LEFT = 1 << 2;
+        const CONTROL_RIGHT = 1 << 3;
+        /// The left Alt, `Key::Alt`.
+        const ALT = 1 << 4;
+        /// The right Alt, `Key::AltGr`. Layouts see it as Ctrl+Alt, so it is
+        /// reported with `CONTROL_LEFT`, like Windows does.
+        const ALT_GR = 1 << 5;
+        const META_LEFT = 1 << 6;
+        const META_RIGHT = 1 << 7;
+        const CAPS_LOCK = 1 << 8;
+        const NUM_LOCK = 1 << 9;
+        const SCROLL_LOCK = 1 << 10;
+
+        const SHIFT = Self::SHIFT_LEFT.bits() | Self::SHIFT_RIGHT.bits();
+        const CONTROL = Self::CONTROL_LEFT.bits() | Self::CONTROL_RIGHT.bits();
+        const META = Self::META_LEFT.bits() | Self::META_RIGHT.bits();
+        /// The lock keys, which are toggled rather than held.
+        const LOCKS = Self::CAPS_LOCK.bits() | Self::NUM_LOCK.bits() | Self::SCROLL_LOCK.bits();
+    }
+}
+
+/// The keys whose state tells whether each modifier is held.
+pub(crate) const HELD_KEYS: [Key; 8] = [
+    Key::ShiftLeft,
+    Key::ShiftRight,
+    Key::ControlLeft,
+    Key::ControlRight,
+    Key::Alt,
+    Key::AltGr,
+    Key::MetaLeft,
+    Key::MetaRight,
+];
+
+/// The keys whose toggle state tells whether each lock is on.
+pub(crate) const LOCK_KEYS: [Key; 3] = [Key::CapsLock, Key::NumLock, Key::ScrollLock];
+
+impl Modifiers {
+    /// Reads the modifiers from a keyboard state: `is_down` tells whether a
+    /// key of `HELD_KEYS` is held, `is_toggled` whether a key of `LOCK_KEYS`
+    /// is on.
+    pub(crate) fn from_state<D, T>(is_down: D, is_toggled: T) -> Modifiers
+    where
+        D: Fn(Key) -> bool,
+        T: Fn(Key) -> bool,
+    {
+        let held = HELD_KEYS.into_iter().filter(|key| is_down(*key));
+        let toggled = LOCK_KEYS.into_iter().filter(|key| is_toggled(*key));
+        held.chain(toggled)
+            .map(Modifiers::from_key)
+            .collect::<Modifiers>()
+            .with_alt_gr_control()
+    }
+
+    /// Adds the left Control that AltGr holds.
+    fn with_alt_gr_control(self) -> Modifiers {
+        if self.contains(Modifiers::ALT_GR) {
+            self | Modifiers::CONTROL_LEFT
+        } else {
+            self
+        }
+    }
+
+    /// The flag of a modifier or lock key, empty for the other keys.
+    pub fn from_key(key: Key) -> Modifiers {
+        match key {
+            Key::ShiftLeft => Modifiers::SHIFT_LEFT,
+            Key::ShiftRight => Modifiers::SHIFT_RIGHT,
+            Key::ControlLeft => Modifiers::CONTROL_LEFT,
+            Key::ControlRight => Modifiers::CONTROL_RIGHT,
+            Key::Alt => Modifiers::ALT,
+            Key::AltGr => Modifiers::ALT_GR,
+            Key::MetaLeft => Modifiers::META_LEFT,
+            Key::MetaRight => Modifiers::META_RIGHT,
+            Key::CapsLock => Modifiers::CAPS_LOCK,
+            Key::NumLock => Modifiers::NUM_LOCK,
+            Key::ScrollLock => Modifiers::SCROLL_LOCK,
+            _ => Modifiers::empty(),
+        }
+    }
+
+    pub fn shift(self) -> bool {
+        self.intersects(Modifiers::SHIFT)
+    }
+
+    pub fn ctrl(self) -> bool {
+        self.intersects(Modifiers::CONTROL)
+    }
+
+    /// Either Alt, AltGr included.
+    pub fn alt(self) -> bool {
+        self.intersects(Modifiers::ALT | Modifiers::ALT_GR)
+    }
+
+    pub fn meta(self) -> bool {
+        self.intersects(Modifiers::META)
+    }
+}
+
+/// Keeps `Modifiers` up to date from a sequence of events, without touching
+/// the OS. Listeners seed one with the key state of the OS when the hook is
+/// installed, so modifiers held at that time are not missed.
+///
+/// ```
+/// use win_binder::{EventType, Key, ModifierTracker, Modifiers};
+///
+/// let mut tracker = ModifierTracker::new(Modifiers::NUM_LOCK);
+/// tracker.update(&EventType::KeyPress(Key::ControlRight));
+/// tracker.update(&EventType::KeyPress(Key::NumLock));
+/// assert_eq!(tracker.modifiers(), Modifiers::CONTROL_RIGHT);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct ModifierTracker {
+    modifiers: Modifiers,
+    /// The lock keys currently held down, so auto-repeat doesn't toggle them.
+    locks_down: Modifiers,
+}
+
+impl ModifierTracker {
+    pub fn new(modifiers: Modifiers) -> ModifierTracker {
+        ModifierTracker {
+            modifiers,
+            locks_down: Modifiers::empty(),
+        }
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers.with_alt_gr_control()
+    }
+
+    /// Applies an event and returns the modifiers right after it: a press
+    /// of Shift is reported with Shift held, its release without.
+    pub fn update(&mut self, event_type: &EventType) -> Modifiers {
+        let (key, pressed) = match event_type {
+            EventType::KeyPress(key) => (*key, true),
+            EventType::KeyRelease(key) => (*key, false),
+            _ => return self.modifiers(),
+        };
+        let flag = Modifiers::from_key(key);
+        if Modifiers::LOCKS.intersects(flag) {
+            if pressed && !self.locks_down.contains(flag) {
+                self.modifiers.toggle(flag);
+            }
+            self.locks_down.set(flag, pressed);
+        } else {
+            self.modifiers.set(flag, pressed);
+        }
+        self.modifiers()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(tracker: &mut ModifierTracker, events: &[EventType]) -> Modifiers {
+        for event_type in events {
+            tracker.update(event_type);
+        }
+        tracker.modifiers()
+    }
+
+    #[test]
+    fn test_from_key() {
+        assert_eq!(Modifiers::from_key(Key::AltGr), Modifiers::ALT_GR);
+        assert_eq!(Modifiers::from_key(Key::NumLock), Modifiers::NUM_LOCK);
+        assert_eq!(Modifiers::from_key(Key::KeyA), Modifiers::empty());
+    }
+
+    #[test]
+    fn test_groups() {
+        assert!(Modifiers::SHIFT_RIGHT.shift());
+        assert!(Modifiers::ALT_GR.alt());
+        assert!(!Modifiers::ALT_GR.ctrl());
+        assert!(Modifiers::META_LEFT.meta());
+        assert!(!Modifiers::LOCKS.shift());
+        assert!(!(Modifiers::CAPS_LOCK | Modifiers::NUM_LOCK).ctrl());
+    }
+
+    #[test]
+    fn test_tracker_held() {
+        let mut tracker = ModifierTracker::default();
+        assert_eq!(
+            tracker.update(&EventType::KeyPress(Key::ShiftLeft)),
+            Modifiers::SHIFT_LEFT
+        );
+        assert_eq!(
+            feed(
+                &mut tracker,
+                &[
+                    EventType::KeyPress(Key::AltGr),
+                    EventType::KeyPress(Key::KeyA),
+                    EventType::MouseMove { x: 1.0, y: 1.0 },
+                ]
+            ),
+            Modifiers::SHIFT_LEFT | Modifiers::ALT_GR | Modifiers::CONTROL_LEFT
+        );
+        assert_eq!(
+            tracker.update(&EventType::KeyRelease(Key::ShiftLeft)),
+            Modifiers::ALT_GR | Modifiers::CONTROL_LEFT
+        );
+        // A release we never saw the press of, the key was held before.
+        assert_eq!(
+            tracker.update(&EventType::KeyRelease(Key::ControlRight)),
+            Modifiers::ALT_GR | Modifiers::CONTROL_LEFT
+        );
+        // Releasing the left Control doesn't release the one of AltGr.
+        assert_eq!(
+            feed(
+                &mut tracker,
+                &[
+                    EventType::KeyPress(Key::ControlLeft),
+                    EventType::KeyRelease(Key::ControlLeft),
+                ]
+            ),
+            Modifiers::ALT_GR | Modifiers::CONTROL_LEFT
+        );
+        assert_eq!(
+            tracker.update(&EventType::KeyRelease(Key::AltGr)),
+            Modifiers::empty()
+        );
+    }
+
+    #[test]
+    fn test_from_state() {
+        let down = [Key::ShiftRight, Key::AltGr, Key::KeyA, Key::NumLock];
+        assert_eq!(
+            Modifiers::from_state(|key| down.contains(&key), |key| key == Key::CapsLock),
+            Modifiers::SHIFT_RIGHT
+                | Modifiers::ALT_GR
+                | Modifiers::CONTROL_LEFT
+                | Modifiers::CAPS_LOCK
+        );
+        assert_eq!(
+            Modifiers::from_state(|_| false, |_| false),
+            Modifiers::empty()
+        );
+    }
+
+    #[test]
+    fn test_tracker_seeded() {
+        let mut tracker = ModifierTracker::new(Modifiers::CONTROL_LEFT | Modifiers::CAPS_LOCK);
+        assert_eq!(
+            tracker.update(&EventType::KeyPress(Key::KeyC)),
+            Modifiers::CONTROL_LEFT | Modifiers::CAPS_LOCK
+        );
+        assert_eq!(
+            tracker.update(&EventType::KeyRelease(Key::ControlLeft)),
+            Modifiers::CAPS_LOCK
+        );
+    }
+
+    #[test]
+    fn test_tracker_locks() {
+        let mut tracker = ModifierTracker::new(Modifiers::NUM_LOCK);
+        assert_eq!(
+            feed(
+                &mut tracker,
+                &[
+                    EventType::KeyPress(Key::CapsLock),
+                    // Auto-repeat
+                    EventType::KeyPress(Key::CapsLock),
+                    EventType::KeyRelease(Key::CapsLock),
+                    EventType::KeyPress(Key::NumLock),
+                    EventType::KeyRelease(Key::NumLock),
+                ]
+            ),
+            Modifiers::CAPS_LOCK
+        );
+        assert_eq!(
+            feed(
+                &mut tracker,
+                &[
+                    EventType::KeyPress(Key::CapsLock),
+                    EventType::KeyRelease(Key::CapsLock),
+                    EventType::KeyPress(Key::ScrollLock),
+                ]
+            ),
+            Modifiers::SCROLL_LOCK
+        );
+    }
+}
@@ -0,0 +1,507 @@
+use crate::win_binder::Button;
+use crate::win_binder::Key;
+use crate::win_binder::ParseNameError;
+use std::fmt;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Each key with its canonical name, used by `Display`, and the aliases also
+/// accepted by `FromStr`. Names are matched case-insensitively.
+///
+/// These names are a stable contract for config files: a name can be added
+/// here but never changed or removed, whatever happens to the variants.
+const KEY_NAMES: &[(Key, &str, &[&str])] = &[
+    (Key::KeyA, "A", &["KeyA"]),
+    (Key::KeyB, "B", &["KeyB"]),
+    (Key::KeyC, "C", &["KeyC"]),
+    (Key::KeyD, "D", &["KeyD"]),
+    (Key::KeyE, "E", &["KeyE"]),
+    (Key::KeyF, "F", &["KeyF"]),
+    (Key::KeyG, "G", &["KeyG"]),
+    (Key::KeyH, "H", &["KeyH"]),
+    (Key::KeyI, "I", &["KeyI"]),
+    (Key::KeyJ, "J", &["KeyJ"]),
+    (Key::KeyK, "K", &["KeyK"]),
+    (Key::KeyL, "L", &["KeyL"]),
+    (Key::KeyM, "M", &["KeyM"]),
+    (Key::KeyN, "N", &["KeyN"]),
+    (Key::KeyO, "O", &["KeyO"]),
+    (Key::KeyP, "P", &["KeyP"]),
+    (Key::KeyQ, "Q", &["KeyQ"]),
+    (Key::KeyR, "R", &["KeyR"]),
+    (Key::KeyS, "S", &["KeyS"]),
+    (Key::KeyT, "T", &["KeyT"]),
+    (Key::KeyU, "U", &["KeyU"]),
+    (Key::KeyV, "V", &["KeyV"]),
+    (Key::KeyW, "W", &["KeyW"]),
+    (Key::KeyX, "X", &["KeyX"]),
+    (Key::KeyY, "Y", &["KeyY"]),
+    (Key::KeyZ, "Z", &["KeyZ"]),
+    (Key::Num0, "0", &["Num0", "Digit0"]),
+    (Key::Num1, "1", &["Num1", "Digit1"]),
+    (Key::Num2, "2", &["Num2", "Digit2"]),
+    (Key::Num3, "3", &["Num3", "Digit3"]),
+    (Key::Num4, "4", &["Num4", "Digit4"]),
+    (Key::Num5, "5", &["Num5", "Digit5"]),
+    (Key::Num6, "6", &["Num6", "Digit6"]),
+    (Key::Num7, "7", &["Num7", "Digit7"]),
+    (Key::Num8, "8", &["Num8", "Digit8"]),
+    (Key::Num9, "9", &["Num9", "Digit9"]),
+    (Key::F1, "F1", &[]),
+    (Key::F2, "F2", &[]),
+    (Key::F3, "F3", &[]),
+    (Key::F4, "F4", &[]),
+    (Key::F5, "F5", &[]),
+    (Key::F6, "F6", &[]),
+    (Key::F7, "F7", &[]),
+    (Key::F8, "F8", &[]),
+    (Key::F9, "F9", &[]),
+    (Key::F10, "F10", &[]),
+    (Key::F11, "F11", &[]),
+    (Key::F12, "F12", &[]),
+    (Key::F13, "F13", &[]),
+    (Key::F14, "F14", &[]),
+    (Key::F15, "F15", &[]),
+    (Key::F16, "F16", &[]),
+    (Key::F17, "F17", &[]),
+    (Key::F18, "F18", &[]),
+    (Key::F19, "F19", &[]),
+    (Key::F20, "F20", &[]),
+    (Key::F21, "F21", &[]),
+    (Key::F22, "F22", &[]),
+    (Key::F23, "F23", &[]),
+    (Key::F24, "F24", &[]),
+    (
+        Key::ControlLeft,
+        "LCtrl",
+        &["Ctrl", "Control", "LControl", "LeftCtrl", "LeftControl"],
+    ),
+    (
+        Key::ControlRight,
+        "RCtrl",
+        &["RControl", "RightCtrl", "RightControl"],
+    ),
+    (Key::ShiftLeft, "LShift", &["Shift", "LeftShift"]),
+    (Key::ShiftRight, "RShift", &["RightShift"]),
+    (Key::Alt, "LAlt", &["Alt", "LeftAlt", "LMenu", "Option"]),
+    (Key::AltGr, "AltGr", &["RAlt", "RightAlt", "RMenu"]),
+    (
+        Key::MetaLeft,
+        "LMeta",
+        &[
+            "Meta", "LeftMeta", "Win", "LWin", "Windows", "Super", "Cmd", "Command",
+        ],
+    ),
+    (Key::MetaRight, "RMeta", &["RightMeta", "RWin"]),
+    (Key::Function, "Fn", &["Function"]),
+    (Key::ContextMenu, "ContextMenu", &["Apps"]),
+    (Key::Space, "Space", &["Spacebar"]),
+    (Key::Tab, "Tab", &[]),
+    (Key::Return, "Enter", &["Return"]),
+    (Key::Escape, "Escape", &["Esc"]),
+    (Key::Backspace, "Backspace", &["Back", "BS"]),
+    (Key::Insert, "Insert", &["Ins"]),
+    (Key::Delete, "Delete", &["Del"]),
+    (Key::Home, "Home", &[]),
+    (Key::End, "End", &[]),
+    (Key::PageUp, "PageUp", &["PgUp", "Prior"]),
+    (Key::PageDown, "PageDown", &["PgDn", "Next"]),
+    (Key::UpArrow, "Up", &["UpArrow", "ArrowUp"]),
+    (Key::DownArrow, "Down", &["DownArrow", "ArrowDown"]),
+    (Key::LeftArrow, "Left", &["LeftArrow", "ArrowLeft"]),
+    (Key::RightArrow, "Right", &["RightArrow", "ArrowRight"]),
+    (Key::CapsLock, "CapsLock", &["Caps", "Capital"]),
+    (Key::NumLock, "NumLock", &[]),
+    (Key::ScrollLock, "ScrollLock", &["Scroll"]),
+    (
+        Key::PrintScreen,
+        "PrintScreen",
+        &["PrtSc", "PrtScn", "Snapshot", "SysRq"],
+    ),
+    (Key::Pause, "Pause", &["Break"]),
+    (Key::BackQuote, "Backquote", &["`", "Grave", "Tilde"]),
+    (Key::Minus, "Minus", &["-", "Dash"]),
+    (Key::Equal, "Equal", &["=", "Equals"]),
+    (Key::LeftBracket, "LeftBracket", &["[", "BracketLeft"]),
+    (Key::RightBracket, "RightBracket", &["]", "BracketRight"]),
+    (Key::SemiColon, "Semicolon", &[";"]),
+    (Key::Quote, "Quote", &["'", "Apostrophe"]),
+    (Key::BackSlash, "Backslash", &["\\"]),
+    (Key::IntlBackslash, "IntlBackslash", &["Oem102"]),
+    (Key::Comma, "Comma", &[","]),
+    (Key::Dot, "Period", &[".", "Dot"]),
+    (Key::Slash, "Slash", &["/"]),
+    (Key::Oem8, "Oem8", &[]),
+    (Key::Kp0, "Numpad0", &["Kp0"]),
+    (Key::Kp1, "Numpad1", &["Kp1"]),
+    (Key::Kp2, "Numpad2", &["Kp2"]),
+    (Key::Kp3, "Numpad3", &["Kp3"]),
+    (Key::Kp4, "Numpad4", &["Kp4"]),
+    (Key::Kp5, "Numpad5", &["Kp5"]),
+    (Key::Kp6, "Numpad6", &["Kp6"]),
+    (Key::Kp7, "Numpad7", &["Kp7"]),
+    (Key::Kp8, "Numpad8", &["Kp8"]),
+    (Key::Kp9, "Numpad9", &["Kp9"]),
+    (Key::KpPlus, "NumpadAdd", &["NumpadPlus", "KpPlus"]),
+    (Key::KpMinus, "NumpadSubtract", &["NumpadMinus", "KpMinus"]),
+    (Key::KpMultiply, "NumpadMultiply", &["KpMultiply"]),
+    (Key::KpDivide, "NumpadDivide", &["KpDivide"]),
+    (
+        Key::KpDelete,
+        "NumpadDecimal",
+        &["NumpadDelete", "KpDelete", "KpDecimal"],
+    ),
+    (Key::KpSeparator, "NumpadSeparator", &["KpSeparator"]),
+    (Key::KpReturn, "NumpadEnter", &["KpEnter", "KpReturn"]),
+    (Key::VolumeMute, "VolumeMute", &["Mute"]),
+    (Key::VolumeDown, "VolumeDown", &["VolDown"]),
+    (Key::VolumeUp, "VolumeUp", &["VolUp"]),
+    (
+        Key::MediaNextTrack,
+        "MediaNext",
+        &["MediaNextTrack", "NextTrack"],
+    ),
+    (
+        Key::MediaPrevTrack,
+        "MediaPrevious",
+        &["MediaPrevTrack", "PrevTrack"],
+    ),
+    (Key::MediaStop, "MediaStop", &[]),
+    (Key::MediaPlayPause, "MediaPlayPause", &["PlayPause"]),
+    (Key::BrowserBack, "BrowserBack", &[]),
+    (Key::BrowserForward, "BrowserForward", &[]),
+    (Key::BrowserRefresh, "BrowserRefresh", &[]),
+    (Key::BrowserStop, "BrowserStop", &[]),
+    (Key::BrowserSearch, "BrowserSearch", &[]),
+    (Key::BrowserFavorites, "BrowserFavorites", &[]),
+    (Key::BrowserHome, "BrowserHome", &[]),
+    (Key::LaunchMail, "LaunchMail", &["Mail"]),
+    (
+        Key::LaunchMediaSelect,
+        "LaunchMediaSelect",
+        &["MediaSelect"],
+    ),
+    (Key::LaunchApp1, "LaunchApp1", &[]),
+    (Key::LaunchApp2, "LaunchApp2", &[]),
+    (Key::Sleep, "Sleep", &[]),
+    (Key::Cancel, "Cancel", &[]),
+    (Key::Clear, "Clear", &[]),
+    (Key::Select, "Select", &[]),
+    (Key::Print, "Print", &[]),
+    (Key::Execute, "Execute", &[]),
+    (Key::Help, "Help", &[]),
+    (Key::Kana, "Kana", &["Hangul"]),
+    (Key::Kanji, "Kanji", &["Hanja"]),
+    (Key::Junja, "Junja", &[]),
+    (Key::Final, "Final", &[]),
+    (Key::ImeOn, "ImeOn", &[]),
+    (Key::ImeOff, "ImeOff", &[]),
+    (Key::Convert, "Convert", &[]),
+    (Key::NonConvert, "NonConvert", &[]),
+    (Key::Accept, "Accept", &[]),
+    (Key::ModeChange, "ModeChange", &[]),
+    (Key::ImeProcess, "ImeProcess", &["ProcessKey"]),
+    (Key::Attn, "Attn", &[]),
+    (Key::CrSel, "CrSel", &[]),
+    (Key::ExSel, "ExSel", &[]),
+    (Key::EraseEof, "EraseEof", &[]),
+    (Key::Play, "Play", &[]),
+    (Key::Zoom, "Zoom", &[]),
+    (Key::Pa1, "Pa1", &[]),
+    (Key::OemClear, "OemClear", &[]),
+];
+
+const BUTTON_NAMES: &[(Button, &str, &[&str])] = &[
+    (Button::Left, "Left", &["Mouse1", "LeftButton", "LMB"]),
+    (Button::Right, "Right", &["Mouse2", "RightButton", "RMB"]),
+    (Button::Middle, "Middle", &["Mouse3", "MiddleButton", "MMB"]),
+    (Button::Back, "Back", &["Mouse4", "XButton1", "Backward"]),
+    (Button::Forward, "Forward", &["Mouse5", "XButton2"]),
+];
+
+/// `Unknown` variants are named `Unknown(<code>)` for keys, and after the
+/// mouse button number for buttons: `Unknown(3)` is `Mouse6`. `Unknown(1)`
+/// and `Unknown(2)` are the same buttons as `Back` and `Forward` and are
+/// named like them, so they parse back as `Back` and `Forward`: the round
+/// trip gives their `Button::normalize`. `Unknown(0)` comes before `Mouse4`
+/// and has no number, so it is named `Unknown(0)` like keys.
+const UNKNOWN_KEY: &str = "Unknown";
+const UNKNOWN_BUTTON: &str = "Mouse";
+const FIRST_UNKNOWN_BUTTON: u16 = 3;
+
+/// How many suggestions a parse error gives at most.
+const MAX_SUGGESTIONS: usize = 3;
+
+fn find<T: Copy>(names: &[(T, &'static str, &[&'static str])], name: &str) -> Option<T> {
+    names
+        .iter()
+        .find(|(_, canonical, aliases)| {
+            canonical.eq_ignore_ascii_case(name)
+                || aliases.iter().any(|alias| alias.eq_ignore_ascii_case(name))
+        })
+        .map(|(value, _, _)| *value)
+}
+
+fn canonical<T: PartialEq>(
+    names: &[(T, &'static str, &[&'static str])],
+    value: &T,
+) -> Option<&'static str> {
+    names
+        .iter()
+        .find(|(candidate, _, _)| candidate == value)
+        .map(|(_, canonical, _)| *canonical)
+}
+
+/// Number of single character insertions, deletions and substitutions
+/// between `a` and `b`, ignoring ASCII case.
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().map(|c| c.to_ascii_lowercase()).collect();
+    let b: Vec<char> = b.chars().map(|c| c.to_ascii_lowercase()).collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// The canonical names closest to `name`, best first, counting both the
+/// canonical names and the aliases.
+fn suggestions<T>(names: &[(T, &'static str, &[&'static str])], name: &str) -> Vec<&'static str> {
+    let threshold = (name.chars().count() / 3).max(1);
+    let mut scored: Vec<(usize, &'static str)> = names
+        .iter()
+        .filter_map(|(_, canonical, aliases)| {
+            std::iter::once(canonical)
+                .chain(aliases.iter())
+                .map(|candidate| distance(name, candidate))
+                .min()
+                .filter(|score| *score <= threshold)
+                .map(|score| (score, *canonical))
+        })
+        .collect();
+    scored.sort_by_key(|(score, _)| *score);
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, canonical)| canonical)
+        .collect()
+}
+
+/// Parses the `<code>` out of `<prefix>(<code>)` or `<prefix><code>`.
+fn numbered<T: FromStr>(name: &str, prefix: &str) -> Option<T> {
+    if !name.get(..prefix.len())?.eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+    let rest = &name[prefix.len()..];
+    let rest = rest
+        .strip_prefix('(')
+        .and_then(|rest| rest.strip_suffix(')'))
+        .unwrap_or(rest);
+    rest.parse().ok()
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self, canonical(KEY_NAMES, self)) {
+            (_, Some(name)) => f.write_str(name),
+            (Key::Unknown(code), None) => write!(f, "{}({})", UNKNOWN_KEY, code),
+            (key, None) => write!(f, "{:?}", key),
+        }
+    }
+}
+
+/// Parses the names given by `Display` and their aliases, for instance
+/// `"Esc"`, `"lctrl"` or `"PgDn"`, ignoring case.
+///
+/// ```
+/// use win_binder::Key;
+///
+/// assert_eq!("esc".parse::<Key>().unwrap(), Key::Escape);
+/// assert_eq!(Key::PageDown.to_string(), "PageDown");
+/// let error = "Escpae".parse::<Key>().unwrap_err();
+/// assert_eq!(error.suggestions, vec!["Escape"]);
+/// ```
+impl FromStr for Key {
+    type Err = ParseNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim();
+        if let Some(key) = find(KEY_NAMES, name) {
+            return Ok(key);
+        }
+        if let Some(code) = numbered(name, UNKNOWN_KEY) {
+            return Ok(Key::Unknown(code));
+        }
+        Err(ParseNameError {
+            name: name.to_string(),
+            suggestions: suggestions(KEY_NAMES, name),
+        })
+    }
+}
+
+impl Display for Button {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self, canonical(BUTTON_NAMES, self)) {
+            (_, Some(name)) => f.write_str(name),
+            (Button::Unknown(0), None) => write!(f, "{}(0)", UNKNOWN_KEY),
+            (Button::Unknown(code), None) => write!(
+                f,
+                "{}{}",
+                UNKNOWN_BUTTON,
+                u16::from(*code) + FIRST_UNKNOWN_BUTTON
+            ),
+            (button, None) => write!(f, "{:?}", button),
+        }
+    }
+}
+
+/// Parses the names given by `Display` and their aliases, for instance
+/// `"Left"`, `"mmb"` or `"mouse4"`, ignoring case. Numbers from `Mouse6`
+/// give `Unknown` buttons, and `Unknown(0)` gives `Unknown(0)`. The names of
+/// `Unknown(1)` and `Unknown(2)` give `Back` and `Forward`.
+impl FromStr for Button {
+    type Err = ParseNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim();
+        if let Some(button) = find(BUTTON_NAMES, name) {
+            return Ok(button);
+        }
+        if numbered::<u8>(name, UNKNOWN_KEY) == Some(0) {
+            return Ok(Button::Unknown(0));
+        }
+        let code = numbered::<u16>(name, UNKNOWN_BUTTON)
+            .and_then(|number| number.checked_sub(FIRST_UNKNOWN_BUTTON))
+            .and_then(|code| u8::try_from(code).ok());
+        match code {
+            Some(code) if code > 0 => Ok(Button::Unknown(code)),
+            _ => Err(ParseNameError {
+                name: name.to_string(),
+                suggestions: suggestions(BUTTON_NAMES, name),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keycodes::KEYS;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_every_key_has_a_name() {
+        let extra = [Key::KpReturn, Key::Function];
+        for key in KEYS.iter().chain(extra.iter()) {
+            assert!(canonical(KEY_NAMES, key).is_some(), "{:?}", key);
+        }
+        assert_eq!(KEY_NAMES.len(), KEYS.len() + extra.len());
+    }
+
+    #[test]
+    fn test_names_are_unique() {
+        let mut seen = HashSet::new();
+        for (_, canonical, aliases) in KEY_NAMES {
+            for name in std::iter::once(canonical).chain(aliases.iter()) {
+                assert!(seen.insert(name.to_ascii_lowercase()), "{}", name);
+            }
+        }
+        let mut seen = HashSet::new();
+        for (_, canonical, aliases) in BUTTON_NAMES {
+            for name in std::iter::once(canonical).chain(aliases.iter()) {
+                assert!(seen.insert(name.to_ascii_lowercase()), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_keys_round_trip() {
+        for (key, _, aliases) in KEY_NAMES {
+            assert_eq!(key.to_string().parse::<Key>(), Ok(*key));
+            for alias in *aliases {
+                assert_eq!(alias.parse::<Key>(), Ok(*key));
+            }
+        }
+        for key in [Key::Unknown(0), Key::Unknown(0xFF), Key::Unknown(u32::MAX)] {
+            assert_eq!(key.to_string().parse::<Key>(), Ok(key));
+        }
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!("ctrl".parse::<Key>(), Ok(Key::ControlLeft));
+        assert_eq!("LCTRL".parse::<Key>(), Ok(Key::ControlLeft));
+        assert_eq!(" pgdn ".parse::<Key>(), Ok(Key::PageDown));
+        assert_eq!("unknown(233)".parse::<Key>(), Ok(Key::Unknown(233)));
+        assert_eq!("Unknown233".parse::<Key>(), Ok(Key::Unknown(233)));
+        assert_eq!(Key::Unknown(233).to_string(), "Unknown(233)");
+        assert_eq!(Key::Escape.to_string(), "Escape");
+        assert_eq!(Key::Num7.to_string(), "7");
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let error = "Escpae".parse::<Key>().unwrap_err();
+        assert_eq!(error.name, "Escpae");
+        assert_eq!(error.suggestions, vec!["Escape"]);
+
+        let error = "pgdwn".parse::<Key>().unwrap_err();
+        assert_eq!(error.suggestions, vec!["PageDown"]);
+
+        let error = "F25".parse::<Key>().unwrap_err();
+        assert_eq!(error.suggestions.len(), MAX_SUGGESTIONS);
+        assert!(error.suggestions.contains(&"F2"));
+
+        let error = "Hyperspace".parse::<Key>().unwrap_err();
+        assert!(error.suggestions.is_empty());
+        assert_eq!(error.to_string(), "Unknown name \"Hyperspace\"");
+
+        let error = "Lef".parse::<Button>().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown name \"Lef\", did you mean \"Left\"?"
+        );
+    }
+
+    #[test]
+    fn test_buttons() {
+        for (button, _, aliases) in BUTTON_NAMES {
+            assert_eq!(button.to_string().parse::<Button>(), Ok(*button));
+            for alias in *aliases {
+                assert_eq!(alias.parse::<Button>(), Ok(*button));
+            }
+        }
+        assert_eq!("mouse4".parse::<Button>(), Ok(Button::Back));
+        assert_eq!("XButton2".parse::<Button>(), Ok(Button::Forward));
+        assert_eq!(Button::Unknown(2).to_string(), "Mouse5");
+        assert_eq!(Button::Unknown(0).to_string(), "Unknown(0)");
+        for button in [
+            Button::Unknown(0),
+            Button::Unknown(1),
+            Button::Unknown(2),
+            Button::Unknown(3),
+        ] {
+            assert_eq!(button.to_string().parse(), Ok(button.normalize()));
+        }
+        assert_eq!("mouse6".parse::<Button>(), Ok(Button::Unknown(3)));
+        assert_eq!(Button::Unknown(255).to_string(), "Mouse258");
+        assert_eq!("Mouse258".parse::<Button>(), Ok(Button::Unknown(255)));
+        assert!("Mouse259".parse::<Button>().is_err());
+        assert!("Mouse0".parse::<Button>().is_err());
+        assert!("Unknown(1)".parse::<Button>().is_err());
+    }
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance("", ""), 0);
+        assert_eq!(distance("esc", "ESC"), 0);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "abc"), 3);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::win_binder::OsError;
+
+/// The codes `SetWindowsHookEx` and `SendInput` fail with in practice, with
+/// the message `FormatMessage` gives for them in English.
+static OS_ERRORS: [OsError; 14] = [
+    error(5, "Access is denied"),
+    error(
+        8,
+        "Not enough memory resources are available to process this command",
+    ),
+    error(87, "The parameter is incorrect"),
+    error(126, "The specified module could not be found"),
+    error(1400, "Invalid window handle"),
+    error(1404, "Invalid hook handle"),
+    error(1426, "Invalid hook procedure type"),
+    error(1427, "Invalid hook procedure"),
+    error(1428, "Cannot set nonlocal hook without a module handle"),
+    error(1429, "This hook procedure can only be set globally"),
+    error(1431, "The hook procedure is not installed"),
+    error(1444, "Invalid thread identifier"),
+    error(
+        1450,
+        "Insufficient system resources exist to complete the requested service",
+    ),
+    error(
+        1459,
+        "This operation requires an interactive window station",
+    ),
+];
+
+const fn error(code: u32, message: &'static str) -> OsError {
+    OsError { code, message }
+}
+
+impl OsError {
+    /// The error for a `GetLastError` code, `None` for the codes we don't
+    /// know and for 0, which means no error was reported.
+    ///
+    /// ```
+    /// use win_binder::OsError;
+    ///
+    /// assert_eq!(OsError::from_code(5).unwrap().message, "Access is denied");
+    /// assert_eq!(OsError::from_code(0), None);
+    /// ```
+    pub fn from_code(code: u32) -> Option<&'static OsError> {
+        OS_ERRORS.iter().find(|error| error.code == code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::win_binder::GrabError;
+    use crate::win_binder::ListenError;
+    use std::error::Error;
+
+    #[test]
+    fn test_from_code() {
+        assert_eq!(
+            OsError::from_code(1428).map(|error| error.message),
+            Some("Cannot set nonlocal hook without a module handle")
+        );
+        assert_eq!(OsError::from_code(0), None);
+        assert_eq!(OsError::from_code(1234), None);
+        // Sorted, so a code can't be listed twice.
+        assert!(OS_ERRORS.windows(2).all(|pair| pair[0].code < pair[1].code));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            ListenError::KeyHookError(5).to_string(),
+            "Could not install the keyboard hook: Access is denied (OS error 5)"
+        );
+        assert_eq!(
+            GrabError::MouseHookError(1234).to_string(),
+            "Could not install the mouse hook: OS error 1234"
+        );
+    }
+
+    #[test]
+    fn test_source() {
+        let error = ListenError::MouseHookError(1459);
+        let source = error.source().unwrap().downcast_ref::<OsError>().unwrap();
+        assert_eq!(source.code, 1459);
+        assert!(ListenError::KeyHookError(1234).source().is_none());
+        assert!(ListenError::Reentrant.source().is_none());
+
+        let error = GrabError::IoError(std::io::Error::other("disk"));
+        assert_eq!(error.source().unwrap().to_string(), "disk");
+        assert!(GrabError::KeyHookError(5).source().is_some());
+    }
+}
@@ -0,0 +1,68 @@
+use crate::handle::ListenerHandle;
+use crate::listen::spawn_listen;
+use crate::win_binder::Event;
+use crate::win_binder::ListenError;
+use std::sync::mpsc::channel;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::RecvError;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::mpsc::TryRecvError;
+use std::time::Duration;
+
+/// The receiving end of a background listener, as returned by `events`.
+/// Dropping it unhooks the listener.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use win_binder::events;
+///
+/// let events = events().unwrap();
+/// if let Ok(event) = events.recv_timeout(Duration::from_secs(1)) {
+///     println!("Received {:?}", event);
+/// }
+/// for event in events {
+///     println!("Received {:?}", event);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct EventReceiver {
+    receiver: Receiver<Event>,
+    _handle: ListenerHandle,
+}
+
+impl EventReceiver {
+    /// Blocks until the next event.
+    pub fn recv(&self) -> Result<Event, RecvError> {
+        self.receiver.recv()
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Event, RecvTimeoutError> {
+        self.receiver.recv_timeout(timeout)
+    }
+
+    pub fn try_recv(&self) -> Result<Event, TryRecvError> {
+        self.receiver.try_recv()
+    }
+}
+
+impl Iterator for EventReceiver {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        self.receiver.recv().ok()
+    }
+}
+
+/// Listens on a background thread and returns a receiver for the events.
+/// Returns once the hooks are installed, so no event simulated afterwards is
+/// missed.
+pub fn events() -> Result<EventReceiver, ListenError> {
+    let (sender, receiver) = channel();
+    let handle = spawn_listen(move |event| {
+        let _ = sender.send(event);
+    })?;
+    Ok(EventReceiver {
+        receiver,
+        _handle: handle,
+    })
+}
@@ -0,0 +1,777 @@
+use crate::simulate::simulate;
+use crate::win_binder::Button;
+use crate::win_binder::Event;
+use crate::win_binder::EventType;
+use crate::win_binder::Key;
+use crate::win_binder::RecordError;
+use crate::win_binder::SimulateError;
+use serde::Deserialize;
+use serde::Serialize;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+/// Version of both formats, bumped whenever the way events are written
+/// changes. The binary format depends on the order of the variants of
+/// `EventType`, `Key` and `Button`, so only recordings since the last
+/// reordering can be read. The JSON one only depends on their names, so older
+/// recordings can be read too.
+///
+/// Version 2 added `Button::Back` and `Button::Forward`, which replace the
+/// `Button::Unknown(1)` and `Button::Unknown(2)` of older recordings, version 3
+/// `EventType::MouseMoveRelative` and version 4 the unit of
+/// `EventType::Wheel`. Wheel events of older JSON recordings count notches.
+pub const RECORD_VERSION: u16 = 4;
+
+/// The oldest binary recordings with the same layout as the current ones.
+const BINARY_SINCE_VERSION: u16 = 4;
+
+const BINARY_MAGIC: &[u8; 5] = b"WBREC";
+const JSON_FORMAT: &str = "win_binder";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum RecordFormat {
+    /// A header line then one JSON object per event, readable and stable
+    /// across versions of the crate.
+    #[default]
+    JsonLines,
+    /// A header then the events encoded with `bincode`, much more compact.
+    Binary,
+}
+
+/// An event of a recording, `offset` being the time since the first one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub offset: Duration,
+    pub event_type: EventType,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonHeader {
+    format: String,
+    version: u16,
+}
+
+/// Writes events to a recording, with timestamps relative to the first one.
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::sync::{Arc, Mutex};
+/// use win_binder::{spawn_listen, RecordFormat, Recorder};
+///
+/// let file = File::create("session.jsonl").unwrap();
+/// let recorder = Arc::new(Mutex::new(Recorder::new(file, RecordFormat::JsonLines).unwrap()));
+/// let sink = recorder.clone();
+/// let handle = spawn_listen(move |event| {
+///     sink.lock().unwrap().record(&event).unwrap();
+/// })
+/// .unwrap();
+/// // ...
+/// drop(handle);
+/// recorder.lock().unwrap().flush().unwrap();
+/// ```
+pub struct Recorder<W: Write> {
+    writer: W,
+    format: RecordFormat,
+    start: Option<SystemTime>,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Writes the header of the recording.
+    pub fn new(mut writer: W, format: RecordFormat) -> Result<Recorder<W>, RecordError> {
+        match format {
+            RecordFormat::JsonLines => {
+                let header = JsonHeader {
+                    format: JSON_FORMAT.to_string(),
+                    version: RECORD_VERSION,
+                };
+                serde_json::to_writer(&mut writer, &header)?;
+                writer.write_all(b"\n")?;
+            }
+            RecordFormat::Binary => {
+                writer.write_all(BINARY_MAGIC)?;
+                writer.write_all(&RECORD_VERSION.to_le_bytes())?;
+            }
+        }
+        Ok(Recorder {
+            writer,
+            format,
+            start: None,
+        })
+    }
+
+    /// Records `event`, at the offset of its `time` from the first recorded
+    /// event. Events older than the first one are recorded at offset 0.
+    pub fn record(&mut self, event: &Event) -> Result<(), RecordError> {
+        let start = *self.start.get_or_insert(event.time);
+        self.write(&RecordedEvent {
+            offset: event.time.duration_since(start).unwrap_or_default(),
+            event_type: event.event_type,
+            name: event.name.clone(),
+        })
+    }
+
+    /// Records an event at a given offset, for instance to write a
+    /// recording by hand.
+    pub fn write(&mut self, event: &RecordedEvent) -> Result<(), RecordError> {
+        match self.format {
+            RecordFormat::JsonLines => {
+                serde_json::to_writer(&mut self.writer, event)?;
+                self.writer.write_all(b"\n")?;
+            }
+            RecordFormat::Binary => bincode::serialize_into(&mut self.writer, event)?,
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), RecordError> {
+        Ok(self.writer.flush()?)
+    }
+
+    /// Flushes and returns the writer.
+    pub fn into_inner(mut self) -> Result<W, RecordError> {
+        self.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads the events of a recording in either format, which is detected from
+/// its header.
+pub struct RecordReader<R: Read> {
+    reader: BufReader<R>,
+    format: RecordFormat,
+    version: u16,
+    line: String,
+}
+
+impl<R: Read> RecordReader<R> {
+    pub fn new(reader: R) -> Result<RecordReader<R>, RecordError> {
+        let mut reader = BufReader::new(reader);
+        let (format, version) = if reader.fill_buf()?.starts_with(b"{") {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            let header: JsonHeader =
+                serde_json::from_str(&line).map_err(|_| RecordError::InvalidHeader)?;
+            if header.format != JSON_FORMAT {
+                return Err(RecordError::InvalidHeader);
+            }
+            if header.version == 0 || header.version > RECORD_VERSION {
+                return Err(RecordError::UnsupportedVersion(header.version));
+            }
+            (RecordFormat::JsonLines, header.version)
+        } else {
+            let mut magic = [0; 5];
+            let mut version = [0; 2];
+            reader
+                .read_exact(&mut magic)
+                .map_err(|_| RecordError::InvalidHeader)?;
+            if &magic != BINARY_MAGIC {
+                return Err(RecordError::InvalidHeader);
+            }
+            reader
+                .read_exact(&mut version)
+                .map_err(|_| RecordError::InvalidHeader)?;
+            let version = u16::from_le_bytes(version);
+            if !(BINARY_SINCE_VERSION..=RECORD_VERSION).contains(&version) {
+                return Err(RecordError::UnsupportedVersion(version));
+            }
+            (RecordFormat::Binary, version)
+        };
+        Ok(RecordReader {
+            reader,
+            format,
+            version,
+            line: String::new(),
+        })
+    }
+
+    pub fn format(&self) -> RecordFormat {
+        self.format
+    }
+
+    fn read_event(&mut self) -> Result<Option<RecordedEvent>, RecordError> {
+        match self.format {
+            RecordFormat::JsonLines => loop {
+                self.line.clear();
+                if self.reader.read_line(&mut self.line)? == 0 {
+                    return Ok(None);
+                }
+                if !self.line.trim().is_empty() {
+                    let mut event: RecordedEvent = serde_json::from_str(&self.line)?;
+                    if self.version < 2 {
+                        event.event_type = normalize_buttons(event.event_type);
+                    }
+                    return Ok(Some(event));
+                }
+            },
+            RecordFormat::Binary => {
+                if self.reader.fill_buf()?.is_empty() {
+                    return Ok(None);
+                }
+                Ok(Some(bincode::deserialize_from(&mut self.reader)?))
+            }
+        }
+    }
+}
+
+/// Turns the `Unknown(1)` and `Unknown(2)` side buttons of version 1
+/// recordings into `Back` and `Forward`.
+fn normalize_buttons(event_type: EventType) -> EventType {
+    match event_type {
+        EventType::ButtonPress(button) => EventType::ButtonPress(button.normalize()),
+        EventType::ButtonRelease(button) => EventType::ButtonRelease(button.normalize()),
+        event_type => event_type,
+    }
+}
+
+impl<R: Read> Iterator for RecordReader<R> {
+    type Item = Result<RecordedEvent, RecordError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_event().transpose()
+    }
+}
+
+/// Reads a whole recording.
+pub fn read_recording<R: Read>(reader: R) -> Result<Vec<RecordedEvent>, RecordError> {
+    RecordReader::new(reader)?.collect()
+}
+
+/// How fast `play` replays a recording.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Speed {
+    /// With the recorded delays.
+    #[default]
+    RealTime,
+    /// With the recorded delays divided by the factor: 2.0 plays twice as
+    /// fast. Factors that are not strictly positive play as fast as possible.
+    Factor(f64),
+    /// Without any delay.
+    Unlimited,
+}
+
+impl Speed {
+    fn scale(self, delay: Duration) -> Duration {
+        match self {
+            Speed::RealTime => delay,
+            Speed::Factor(factor) if factor > 0.0 => {
+                Duration::try_from_secs_f64(delay.as_secs_f64() / factor).unwrap_or(Duration::MAX)
+            }
+            Speed::Factor(_) | Speed::Unlimited => Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum State {
+    Playing,
+    Paused,
+    Aborted,
+}
+
+struct Control {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl Control {
+    fn set(&self, state: State) {
+        if let Ok(mut current) = self.state.lock() {
+            // An aborted playback can't be resumed.
+            if *current != State::Aborted {
+                *current = state;
+            }
+        }
+        self.changed.notify_all();
+    }
+
+    /// Waits for `delay` of playing time, not counting the pauses. Returns
+    /// false if the playback was aborted.
+    fn wait(&self, delay: Duration) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        let mut remaining = delay;
+        loop {
+            state = match *state {
+                State::Aborted => return false,
+                State::Playing if remaining.is_zero() => return true,
+                State::Playing => {
+                    let start = Instant::now();
+                    let Ok((state, _)) = self.changed.wait_timeout(state, remaining) else {
+                        return false;
+                    };
+                    remaining = remaining.saturating_sub(start.elapsed());
+                    state
+                }
+                State::Paused => match self.changed.wait(state) {
+                    Ok(state) => state,
+                    Err(_) => return false,
+                },
+            };
+        }
+    }
+}
+
+/// Keys and buttons pressed by the playback and not released yet.
+#[derive(Default)]
+struct Held {
+    keys: Vec<Key>,
+    buttons: Vec<Button>,
+}
+
+impl Held {
+    fn update(&mut self, event_type: &EventType) {
+        match *event_type {
+            EventType::KeyPress(key) if !self.keys.contains(&key) => self.keys.push(key),
+            EventType::KeyRelease(key) => self.keys.retain(|held| *held != key),
+            EventType::ButtonPress(button) if !self.buttons.contains(&button) => {
+                self.buttons.push(button)
+            }
+            EventType::ButtonRelease(button) => self.buttons.retain(|held| *held != button),
+            _ => {}
+        }
+    }
+
+    /// Sends the releases, carrying on if some fail.
+    fn release<F>(&self, send: &mut F)
+    where
+        F: FnMut(&EventType) -> Result<(), SimulateError>,
+    {
+        for release in self.releases() {
+            let _ = send(&release);
+        }
+    }
+
+    fn releases(&self) -> Vec<EventType> {
+        let keys = self
+            .keys
+            .iter()
+            .rev()
+            .map(|key| EventType::KeyRelease(*key));
+        let buttons = self
+            .buttons
+            .iter()
+            .rev()
+            .map(|button| EventType::ButtonRelease(*button));
+        keys.chain(buttons).collect()
+    }
+}
+
+/// Sends the events with `send`, waiting between them as recorded. On abort
+/// or when an event can't be sent, releases whatever was left pressed.
+fn run<F>(
+    events: &[RecordedEvent],
+    speed: Speed,
+    control: &Control,
+    mut send: F,
+) -> Result<(), SimulateError>
+where
+    F: FnMut(&EventType) -> Result<(), SimulateError>,
+{
+    let mut held = Held::default();
+    let mut previous = events.first().map(|event| event.offset).unwrap_or_default();
+    for event in events {
+        let delay = speed.scale(event.offset.saturating_sub(previous));
+        previous = event.offset;
+        if !control.wait(delay) {
+            held.release(&mut send);
+            return Ok(());
+        }
+        if let Err(error) = send(&event.event_type) {
+            held.release(&mut send);
+            return Err(error);
+        }
+        held.update(&event.event_type);
+    }
+    Ok(())
+}
+
+/// Replays a recording on a background thread, as returned by `play`.
+/// Dropping it aborts the playback.
+pub struct Player {
+    control: Arc<Control>,
+    thread: Option<JoinHandle<Result<(), SimulateError>>>,
+}
+
+impl Player {
+    /// Suspends the playback after the current event, without releasing the
+    /// keys it holds.
+    pub fn pause(&self) {
+        self.control.set(State::Paused);
+    }
+
+    pub fn resume(&self) {
+        self.control.set(State::Playing);
+    }
+
+    /// Stops the playback for good, releasing the keys and buttons it holds.
+    pub fn abort(&self) {
+        self.control.set(State::Aborted);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match &self.thread {
+            Some(thread) => thread.is_finished(),
+            None => true,
+        }
+    }
+
+    /// Waits for the end of the playback, returning the error that stopped it
+    /// if an event couldn't be simulated. A panic of the playback thread is
+    /// resumed here.
+    pub fn wait(mut self) -> Result<(), SimulateError> {
+        match self.thread.take().map(|thread| thread.join()) {
+            Some(Ok(result)) => result,
+            Some(Err(panic)) => std::panic::resume_unwind(panic),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Player {
+    fn drop(&mut self) {
+        self.abort();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Replays `events` with `simulate` on a background thread.
+///
+/// ```no_run
+/// use std::fs::File;
+/// use win_binder::{play, read_recording, Speed};
+///
+/// let events = read_recording(File::open("session.jsonl").unwrap()).unwrap();
+/// play(events, Speed::Factor(2.0)).wait().unwrap();
+/// ```
+pub fn play(events: Vec<RecordedEvent>, speed: Speed) -> Player {
+    let control = Arc::new(Control {
+        state: Mutex::new(State::Playing),
+        changed: Condvar::new(),
+    });
+    let thread_control = control.clone();
+    let thread = thread::spawn(move || run(&events, speed, &thread_control, simulate));
+    Player {
+        control,
+        thread: Some(thread),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modifiers::Modifiers;
+    use crate::win_binder::WheelUnit;
+
+    fn recorded(millis: u64, event_type: EventType) -> RecordedEvent {
+        RecordedEvent {
+            offset: Duration::from_millis(millis),
+            event_type,
+            name: None,
+        }
+    }
+
+    fn session() -> Vec<RecordedEvent> {
+        vec![
+            RecordedEvent {
+                name: Some("a".to_string()),
+                ..recorded(0, EventType::KeyPress(Key::KeyA))
+            },
+            recorded(80, EventType::KeyRelease(Key::KeyA)),
+            recorded(120, EventType::MouseMove { x: 10.5, y: -3.0 }),
+            recorded(
+                130,
+                EventType::Wheel {
+                    delta_x: 0,
+                    delta_y: -2,
+                    unit: WheelUnit::Notches,
+                },
+            ),
+            recorded(200, EventType::ButtonPress(Button::Back)),
+            recorded(210, EventType::ButtonRelease(Button::Unknown(3))),
+            recorded(220, EventType::MouseMoveRelative { dx: -4, dy: 7 }),
+            recorded(
+                230,
+                EventType::Wheel {
+                    delta_x: 15,
+                    delta_y: 0,
+                    unit: WheelUnit::Raw,
+                },
+            ),
+        ]
+    }
+
+    fn encode(format: RecordFormat, events: &[RecordedEvent]) -> Vec<u8> {
+        let mut recorder = Recorder::new(vec![], format).unwrap();
+        for event in events {
+            recorder.write(event).unwrap();
+        }
+        recorder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for format in [RecordFormat::JsonLines, RecordFormat::Binary] {
+            let bytes = encode(format, &session());
+            let reader = RecordReader::new(&bytes[..]).unwrap();
+            assert_eq!(reader.format(), format);
+            assert_eq!(read_recording(&bytes[..]).unwrap(), session());
+        }
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let bytes = encode(RecordFormat::JsonLines, &session()[..2]);
+        let text = String::from_utf8(bytes).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], r#"{"format":"win_binder","version":4}"#);
+        assert_eq!(
+            lines[1],
+            r#"{"offset":{"secs":0,"nanos":0},"event_type":{"KeyPress":"KeyA"},"name":"a"}"#
+        );
+        assert_eq!(
+            lines[2],
+            r#"{"offset":{"secs":0,"nanos":80000000},"event_type":{"KeyRelease":"KeyA"},"name":null}"#
+        );
+    }
+
+    #[test]
+    fn test_record_relative_offsets() {
+        let start = SystemTime::now();
+        let event = |millis, event_type| Event {
+            time: start + Duration::from_millis(millis),
+            name: None,
+            event_type,
+            modifiers: Modifiers::empty(),
+            raw: None,
+        };
+        let mut recorder = Recorder::new(vec![], RecordFormat::Binary).unwrap();
+        recorder
+            .record(&event(1000, EventType::KeyPress(Key::KeyA)))
+            .unwrap();
+        recorder
+            .record(&event(1050, EventType::KeyRelease(Key::KeyA)))
+            .unwrap();
+        recorder
+            .record(&event(900, EventType::KeyPress(Key::KeyB)))
+            .unwrap();
+        let bytes = recorder.into_inner().unwrap();
+        assert_eq!(
+            read_recording(&bytes[..]).unwrap(),
+            vec![
+                recorded(0, EventType::KeyPress(Key::KeyA)),
+                recorded(50, EventType::KeyRelease(Key::KeyA)),
+                recorded(0, EventType::KeyPress(Key::KeyB)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_headers() {
+        let mut bytes = encode(RecordFormat::Binary, &session());
+        bytes[5] = 99;
+        assert!(matches!(
+            RecordReader::new(&bytes[..]),
+            Err(RecordError::UnsupportedVersion(99))
+        ));
+        assert!(matches!(
+            RecordReader::new(&b"WBR"[..]),
+            Err(RecordError::InvalidHeader)
+        ));
+        assert!(matches!(
+            RecordReader::new(&b"{\"format\":\"other\",\"version\":1}\n"[..]),
+            Err(RecordError::InvalidHeader)
+        ));
+        assert!(matches!(
+            RecordReader::new(&b"{\"format\":\"win_binder\",\"version\":5}\n"[..]),
+            Err(RecordError::UnsupportedVersion(5))
+        ));
+        let mut bytes = encode(RecordFormat::Binary, &session());
+        bytes[5] = 1;
+        assert!(matches!(
+            RecordReader::new(&bytes[..]),
+            Err(RecordError::UnsupportedVersion(1))
+        ));
+        // Wheel events of version 3 have no unit.
+        bytes[5] = 3;
+        assert!(matches!(
+            RecordReader::new(&bytes[..]),
+            Err(RecordError::UnsupportedVersion(3))
+        ));
+    }
+
+    #[test]
+    fn test_older_json_lines() {
+        let bytes = b"{\"format\":\"win_binder\",\"version\":1}\n\
+            {\"offset\":{\"secs\":1,\"nanos\":0},\"event_type\":{\"ButtonPress\":{\"Unknown\":1}}}\n\
+            {\"offset\":{\"secs\":2,\"nanos\":0},\"event_type\":{\"Wheel\":{\"delta_x\":0,\"delta_y\":1}}}\n";
+        assert_eq!(
+            read_recording(&bytes[..]).unwrap(),
+            vec![
+                recorded(1000, EventType::ButtonPress(Button::Back)),
+                recorded(
+                    2000,
+                    EventType::Wheel {
+                        delta_x: 0,
+                        delta_y: 1,
+                        unit: WheelUnit::Notches
+                    }
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truncated() {
+        for format in [RecordFormat::JsonLines, RecordFormat::Binary] {
+            let bytes = encode(format, &session());
+            let events: Vec<_> = RecordReader::new(&bytes[..bytes.len() - 3])
+                .unwrap()
+                .collect();
+            assert_eq!(events.len(), session().len());
+            assert!(events.last().unwrap().is_err());
+        }
+    }
+
+    #[test]
+    fn test_speed() {
+        let delay = Duration::from_millis(100);
+        assert_eq!(Speed::RealTime.scale(delay), delay);
+        assert_eq!(Speed::Factor(2.0).scale(delay), Duration::from_millis(50));
+        assert_eq!(Speed::Factor(0.5).scale(delay), Duration::from_millis(200));
+        assert_eq!(Speed::Factor(0.0).scale(delay), Duration::ZERO);
+        assert_eq!(Speed::Factor(f64::NAN).scale(delay), Duration::ZERO);
+        assert_eq!(Speed::Factor(1e-300).scale(delay), Duration::MAX);
+        assert_eq!(Speed::Unlimited.scale(delay), Duration::ZERO);
+    }
+
+    fn control(state: State) -> Control {
+        Control {
+            state: Mutex::new(state),
+            changed: Condvar::new(),
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let mut sent = vec![];
+        let result = run(
+            &session(),
+            Speed::Unlimited,
+            &control(State::Playing),
+            |event| {
+                sent.push(*event);
+                Ok(())
+            },
+        );
+        assert!(result.is_ok());
+        let expected: Vec<_> = session().iter().map(|event| event.event_type).collect();
+        assert_eq!(sent, expected);
+    }
+
+    #[test]
+    fn test_run_stops_on_error() {
+        let events = vec![
+            recorded(0, EventType::KeyPress(Key::ShiftLeft)),
+            recorded(0, EventType::ButtonPress(Button::Left)),
+            recorded(0, EventType::KeyPress(Key::KeyA)),
+            recorded(0, EventType::KeyRelease(Key::KeyA)),
+        ];
+        let mut sent = vec![];
+        let result = run(
+            &events,
+            Speed::Unlimited,
+            &control(State::Playing),
+            |event_type| {
+                sent.push(*event_type);
+                if *event_type == EventType::KeyPress(Key::KeyA) {
+                    Err(SimulateError::Blocked {
+                        event_type: Some(*event_type),
+                        os_error: 5,
+                    })
+                } else {
+                    Ok(())
+                }
+            },
+        );
+        assert_eq!(result.unwrap_err().os_error(), Some(5));
+        // What was held is released rather than left stuck.
+        assert_eq!(
+            sent,
+            vec![
+                EventType::KeyPress(Key::ShiftLeft),
+                EventType::ButtonPress(Button::Left),
+                EventType::KeyPress(Key::KeyA),
+                EventType::KeyRelease(Key::ShiftLeft),
+                EventType::ButtonRelease(Button::Left),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pause_resume_abort() {
+        let control = Arc::new(control(State::Playing));
+        let sent = Arc::new(Mutex::new(vec![]));
+        let events = vec![
+            recorded(0, EventType::KeyPress(Key::ShiftLeft)),
+            recorded(0, EventType::ButtonPress(Button::Left)),
+            recorded(30, EventType::KeyPress(Key::KeyA)),
+            recorded(10_000, EventType::KeyRelease(Key::KeyA)),
+        ];
+        let (thread_control, thread_sent) = (control.clone(), sent.clone());
+        control.set(State::Paused);
+        let thread = thread::spawn(move || {
+            run(&events, Speed::RealTime, &thread_control, |event| {
+                thread_sent.lock().unwrap().push(*event);
+                Ok(())
+            })
+        });
+
+        // However slow the thread is, nothing can be sent while paused.
+        thread::sleep(Duration::from_millis(50));
+        assert!(sent.lock().unwrap().is_empty());
+
+        control.set(State::Playing);
+        // The last event is 10 s away, long after the others were sent.
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while sent.lock().unwrap().len() < 3 {
+            assert!(Instant::now() < deadline, "{:?}", sent.lock().unwrap());
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(sent.lock().unwrap().len(), 3);
+
+        control.set(State::Aborted);
+        thread.join().unwrap().unwrap();
+        // Aborting releases what was held, most recent first.
+        assert_eq!(
+            *sent.lock().unwrap(),
+            vec![
+                EventType::KeyPress(Key::ShiftLeft),
+                EventType::ButtonPress(Button::Left),
+                EventType::KeyPress(Key::KeyA),
+                EventType::KeyRelease(Key::KeyA),
+                EventType::KeyRelease(Key::ShiftLeft),
+                EventType::ButtonRelease(Button::Left),
+            ]
+        );
+
+        // An aborted playback stays aborted.
+        control.set(State::Playing);
+        assert!(!control.wait(Duration::ZERO));
+    }
+}
@@ -0,0 +1,827 @@
+use crate::hotkey::Hotkey;
+use crate::hotkey::HotkeyModifier;
+use crate::simulate::simulate_injected_batch;
+use crate::win_binder::BatchError;
+use crate::win_binder::Event;
+use crate::win_binder::EventType;
+use crate::win_binder::Key;
+#[cfg(feature = "config")]
+use crate::win_binder::KeymapError;
+#[cfg(feature = "config")]
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+#[cfg(feature = "config")]
+use std::fs;
+#[cfg(feature = "config")]
+use std::path::Path;
+
+/// Which chord is replaced by which. A source without modifiers (like
+/// `CapsLock`) is remapped whatever modifiers are held, while a chord (like
+/// `Ctrl+H`) only matches exactly.
+///
+/// With the `config` feature, a keymap can be loaded from a TOML or JSON table
+/// of strings:
+///
+/// ```toml
+/// CapsLock = "Escape"
+/// "Ctrl+H" = "Backspace"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Keymap {
+    entries: Vec<(Hotkey, Hotkey)>,
+}
+
+impl Keymap {
+    pub fn new() -> Keymap {
+        Keymap::default()
+    }
+
+    /// Maps `from` to `to`, returning the previous target of `from`.
+    pub fn insert(&mut self, from: Hotkey, to: Hotkey) -> Option<Hotkey> {
+        match self.entries.iter_mut().find(|(source, _)| *source == from) {
+            Some((_, target)) => Some(std::mem::replace(target, to)),
+            None => {
+                self.entries.push((from, to));
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, from: &Hotkey) -> Option<Hotkey> {
+        let index = self.entries.iter().position(|(source, _)| source == from)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    pub fn get(&self, from: &Hotkey) -> Option<Hotkey> {
+        self.entries
+            .iter()
+            .find(|(source, _)| source == from)
+            .map(|(_, target)| *target)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Hotkey, Hotkey)> + '_ {
+        self.entries.iter().copied()
+    }
+
+    #[cfg(feature = "config")]
+    pub fn from_toml(s: &str) -> Result<Keymap, KeymapError> {
+        Keymap::from_entries(toml::from_str(s)?)
+    }
+
+    #[cfg(feature = "config")]
+    pub fn from_json(s: &str) -> Result<Keymap, KeymapError> {
+        Keymap::from_entries(serde_json::from_str(s)?)
+    }
+
+    /// Reads a `.toml` or `.json` file, depending on its extension.
+    #[cfg(feature = "config")]
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Keymap, KeymapError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("toml") => Keymap::from_toml(&content),
+            Some(e) if e.eq_ignore_ascii_case("json") => Keymap::from_json(&content),
+            _ => Err(KeymapError::UnknownFormat(path.to_path_buf())),
+        }
+    }
+
+    #[cfg(feature = "config")]
+    fn from_entries(entries: BTreeMap<String, String>) -> Result<Keymap, KeymapError> {
+        let mut keymap = Keymap::new();
+        for (from, to) in entries {
+            let source = from.parse().map_err(|error| KeymapError::Hotkey {
+                entry: from.clone(),
+                error,
+            })?;
+            let target = to.parse().map_err(|error| KeymapError::Hotkey {
+                entry: to.clone(),
+                error,
+            })?;
+            keymap.insert(source, target);
+        }
+        Ok(keymap)
+    }
+}
+
+impl FromIterator<(Hotkey, Hotkey)> for Keymap {
+    fn from_iter<I: IntoIterator<Item = (Hotkey, Hotkey)>>(iter: I) -> Self {
+        let mut keymap = Keymap::new();
+        for (from, to) in iter {
+            keymap.insert(from, to);
+        }
+        keymap
+    }
+}
+
+/// What to do with an event given to `Remapper::process`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Remap {
+    /// Let the event through unchanged.
+    Pass,
+    /// Swallow the event and send these instead (possibly none).
+    Replace(Vec<EventType>),
+}
+
+/// A source key that is currently held and was remapped.
+#[derive(Clone)]
+struct Active {
+    /// The key that was pressed in place of the source key.
+    key: Key,
+    /// Modifiers we pressed for the target, to release with it.
+    pressed: Vec<Key>,
+    /// Held modifiers we released for the target, to press again after it.
+    released: Vec<Key>,
+}
+
+/// Applies a `Keymap` to a stream of events.
+///
+/// Every remapped press is remembered until its source key is released, so
+/// the release always matches what was pressed, even if the keymap was
+/// changed in between.
+///
+/// ```no_run
+/// use std::sync::{Arc, Mutex};
+/// use win_binder::{spawn_grab, Keymap, Remapper};
+///
+/// let mut keymap = Keymap::new();
+/// keymap.insert("CapsLock".parse().unwrap(), "Escape".parse().unwrap());
+/// let remapper = Arc::new(Mutex::new(Remapper::new(keymap)));
+///
+/// let inner = remapper.clone();
+/// let _handle = spawn_grab(move |event| inner.lock().unwrap().filter(event)).unwrap();
+/// // `remapper.lock().unwrap().set_keymap(...)` can now swap the keymap.
+/// ```
+#[derive(Default)]
+pub struct Remapper {
+    keymap: Keymap,
+    held: Vec<Key>,
+    active: HashMap<Key, Active>,
+}
+
+impl Remapper {
+    pub fn new(keymap: Keymap) -> Remapper {
+        Remapper {
+            keymap,
+            ..Remapper::default()
+        }
+    }
+
+    pub fn keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    /// Keys held while the keymap changes are still released as they were
+    /// pressed.
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
+    }
+
+    /// Forgets every held key, for instance after the grabber was restarted.
+    pub fn reset(&mut self) {
+        self.held.clear();
+        self.active.clear();
+    }
+
+    /// To be called from a `grab` callback: remapped events are swallowed and
+    /// their replacements injected in a single `SendInput` call, which
+    /// grabbers don't see again.
+    ///
+    /// If they can't be injected, the ones that were are undone and the
+    /// remapper goes back to its previous state. A press is then let through
+    /// unmapped rather than lost, and the release of a remapped key becomes
+    /// the release of its target, so that it isn't left stuck down.
+    pub fn filter(&mut self, event: Event) -> Option<Event> {
+        self.filter_with(event, simulate_injected_batch)
+    }
+
+    fn filter_with<F>(&mut self, event: Event, mut send: F) -> Option<Event>
+    where
+        F: FnMut(&[EventType]) -> Result<(), BatchError>,
+    {
+        let held = self.held.clone();
+        let active = self.active.clone();
+        let Remap::Replace(events) = self.process(&event.event_type) else {
+            return Some(event);
+        };
+        let error = send(&events).err()?;
+        let injected = match error {
+            BatchError::Blocked { injected, .. } => injected,
+            BatchError::InvalidEvent { .. } => 0,
+        };
+        if injected > 0 {
+            let undo: Vec<_> = events[..injected].iter().rev().map(inverse).collect();
+            // Best effort: whatever blocked the batch likely blocks this too.
+            let _ = send(&undo);
+        }
+        self.held = held;
+        self.active = active;
+        match event.event_type {
+            EventType::KeyPress(key) => {
+                // The key now reaches the system as itself.
+                if !self.active.contains_key(&key) && !self.held.contains(&key) {
+                    self.held.push(key);
+                }
+                Some(event)
+            }
+            EventType::KeyRelease(key) => {
+                self.held.retain(|held| *held != key);
+                let event_type = match self.active.remove(&key) {
+                    Some(active) => EventType::KeyRelease(active.key),
+                    None => event.event_type,
+                };
+                Some(Event {
+                    event_type,
+                    ..event
+                })
+            }
+            _ => Some(event),
+        }
+    }
+
+    /// Advances the state machine by one event, without sending anything.
+    pub fn process(&mut self, event_type: &EventType) -> Remap {
+        match event_type {
+            EventType::KeyPress(key) => self.press(*key),
+            EventType::KeyRelease(key) => self.release(*key),
+            _ => Remap::Pass,
+        }
+    }
+
+    fn press(&mut self, key: Key) -> Remap {
+        if let Some(active) = self.active.get(&key) {
+            // Auto-repeat of a remapped key.
+            return Remap::Replace(vec![EventType::KeyPress(active.key)]);
+        }
+        if self.held.contains(&key) {
+            return Remap::Pass;
+        }
+
+        let held = self.effective_keys();
+        self.held.push(key);
+        let modifiers = modifiers_of(&held);
+        let (source, target) = match self.keymap.get(&Hotkey::new(&modifiers, key)) {
+            Some(target) => (Hotkey::new(&modifiers, key), target),
+            None => match self.keymap.get(&Hotkey::new(&[], key)) {
+                Some(target) => (Hotkey::new(&[], key), target),
+                None => return Remap::Pass,
+            },
+        };
+
+        let mut events = vec![];
+        let mut released = vec![];
+        let mut kept = vec![];
+        for held_key in held {
+            let key_modifiers = HotkeyModifier::of_key(held_key);
+            if key_modifiers
+                .iter()
+                .any(|modifier| source.has(*modifier) && !target.has(*modifier))
+            {
+                events.push(EventType::KeyRelease(held_key));
+                released.push(held_key);
+            } else {
+                kept.extend_from_slice(key_modifiers);
+            }
+        }
+        let mut pressed = vec![];
+        for modifier in target.modifiers() {
+            if !kept.contains(&modifier) {
+                events.push(EventType::KeyPress(modifier.key()));
+                pressed.push(modifier.key());
+            }
+        }
+        events.push(EventType::KeyPress(target.key()));
+
+        self.active.insert(
+            key,
+            Active {
+                key: target.key(),
+                pressed,
+                released,
+            },
+        );
+        Remap::Replace(events)
+    }
+
+    fn release(&mut self, key: Key) -> Remap {
+        let effective = self.effective_key(key);
+        self.held.retain(|held| *held != key);
+
+        let mut already_released = false;
+        for active in self.active.values_mut() {
+            if let Some(index) = active.released.iter().position(|k| *k == effective) {
+                active.released.remove(index);
+                already_released = true;
+            }
+        }
+
+        match self.active.remove(&key) {
+            Some(active) => {
+                let held = self.effective_keys();
+                let mut events = vec![EventType::KeyRelease(active.key)];
+                for modifier in active.pressed.iter().rev() {
+                    events.push(EventType::KeyRelease(*modifier));
+                }
+                for modifier in active.released {
+                    if held.contains(&modifier) {
+                        events.push(EventType::KeyPress(modifier));
+                    }
+                }
+                Remap::Replace(events)
+            }
+            // This modifier was released on the user's behalf already.
+            None if already_released => Remap::Replace(vec![]),
+            None => Remap::Pass,
+        }
+    }
+
+    /// Held keys, as the rest of the system sees them.
+    fn effective_keys(&self) -> Vec<Key> {
+        self.held
+            .iter()
+            .map(|key| self.effective_key(*key))
+            .collect()
+    }
+
+    fn effective_key(&self, key: Key) -> Key {
+        self.active.get(&key).map_or(key, |active| active.key)
+    }
+}
+
+/// The event undoing a key event that was injected.
+fn inverse(event_type: &EventType) -> EventType {
+    match *event_type {
+        EventType::KeyPress(key) => EventType::KeyRelease(key),
+        EventType::KeyRelease(key) => EventType::KeyPress(key),
+        other => other,
+    }
+}
+
+fn modifiers_of(keys: &[Key]) -> Vec<HotkeyModifier> {
+    let mut modifiers = vec![];
+    for modifier in keys.iter().flat_map(|key| HotkeyModifier::of_key(*key)) {
+        if !modifiers.contains(modifier) {
+            modifiers.push(*modifier);
+        }
+    }
+    modifiers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modifiers::Modifiers;
+    use std::time::SystemTime;
+
+    fn keymap(entries: &[(&str, &str)]) -> Keymap {
+        entries
+            .iter()
+            .map(|(from, to)| (from.parse().unwrap(), to.parse().unwrap()))
+            .collect()
+    }
+
+    fn run(remapper: &mut Remapper, events: &[EventType]) -> Vec<Remap> {
+        events.iter().map(|e| remapper.process(e)).collect()
+    }
+
+    fn event(event_type: EventType) -> Event {
+        Event {
+            time: SystemTime::now(),
+            name: None,
+            event_type,
+            modifiers: Modifiers::empty(),
+            raw: None,
+        }
+    }
+
+    /// Records the batches sent, the first one only injecting `injected`
+    /// events.
+    fn failing_send(
+        sent: &mut Vec<Vec<EventType>>,
+        injected: usize,
+    ) -> impl FnMut(&[EventType]) -> Result<(), BatchError> + '_ {
+        move |events| {
+            sent.push(events.to_vec());
+            if sent.len() > 1 {
+                return Ok(());
+            }
+            Err(BatchError::Blocked {
+                injected,
+                total: events.len(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_single_key() {
+        let mut remapper = Remapper::new(keymap(&[("CapsLock", "Escape")]));
+        assert_eq!(
+            run(
+                &mut remapper,
+                &[
+                    EventType::KeyPress(Key::CapsLock),
+                    EventType::KeyPress(Key::CapsLock),
+                    EventType::KeyRelease(Key::CapsLock),
+                    EventType::KeyPress(Key::KeyA),
+                    EventType::KeyRelease(Key::KeyA),
+                    EventType::ButtonPress(crate::win_binder::Button::Left),
+                ]
+            ),
+            vec![
+                Remap::Replace(vec![EventType::KeyPress(Key::Escape)]),
+                Remap::Replace(vec![EventType::KeyPress(Key::Escape)]),
+                Remap::Replace(vec![EventType::KeyRelease(Key::Escape)]),
+                Remap::Pass,
+                Remap::Pass,
+                Remap::Pass,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_single_key_keeps_held_modifiers() {
+        let mut remapper = Remapper::new(keymap(&[("CapsLock", "Escape")]));
+        assert_eq!(
+            run(
+                &mut remapper,
+                &[
+                    EventType::KeyPress(Key::ShiftLeft),
+                    EventType::KeyPress(Key::CapsLock),
+                    EventType::KeyRelease(Key::CapsLock),
+                    EventType::KeyRelease(Key::ShiftLeft),
+                ]
+            ),
+            vec![
+                Remap::Pass,
+                Remap::Replace(vec![EventType::KeyPress(Key::Escape)]),
+                Remap::Replace(vec![EventType::KeyRelease(Key::Escape)]),
+                Remap::Pass,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chord_source() {
+        let mut remapper = Remapper::new(keymap(&[("Ctrl+H", "Backspace")]));
+        assert_eq!(
+            run(
+                &mut remapper,
+                &[
+                    EventType::KeyPress(Key::ControlLeft),
+                    EventType::KeyPress(Key::KeyH),
+                    EventType::KeyRelease(Key::KeyH),
+                    EventType::KeyRelease(Key::ControlLeft),
+                    EventType::KeyPress(Key::KeyH),
+                    EventType::KeyRelease(Key::KeyH),
+                ]
+            ),
+            vec![
+                Remap::Pass,
+                Remap::Replace(vec![
+                    EventType::KeyRelease(Key::ControlLeft),
+                    EventType::KeyPress(Key::Backspace),
+                ]),
+                Remap::Replace(vec![
+                    EventType::KeyRelease(Key::Backspace),
+                    EventType::KeyPress(Key::ControlLeft),
+                ]),
+                Remap::Pass,
+                Remap::Pass,
+                Remap::Pass,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chord_modifier_released_first() {
+        let mut remapper = Remapper::new(keymap(&[("Ctrl+H", "Backspace")]));
+        assert_eq!(
+            run(
+                &mut remapper,
+                &[
+                    EventType::KeyPress(Key::ControlRight),
+                    EventType::KeyPress(Key::KeyH),
+                    EventType::KeyRelease(Key::ControlRight),
+                    EventType::KeyRelease(Key::KeyH),
+                ]
+            ),
+            vec![
+                Remap::Pass,
+                Remap::Replace(vec![
+                    EventType::KeyRelease(Key::ControlRight),
+                    EventType::KeyPress(Key::Backspace),
+                ]),
+                Remap::Replace(vec![]),
+                Remap::Replace(vec![EventType::KeyRelease(Key::Backspace)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chord_target() {
+        let mut remapper = Remapper::new(keymap(&[("F1", "Ctrl+Shift+C")]));
+        assert_eq!(
+            run(
+                &mut remapper,
+                &[
+                    EventType::KeyPress(Key::ShiftLeft),
+                    EventType::KeyPress(Key::F1),
+                    EventType::KeyRelease(Key::F1),
+                ]
+            ),
+            vec![
+                Remap::Pass,
+                Remap::Replace(vec![
+                    EventType::KeyPress(Key::ControlLeft),
+                    EventType::KeyPress(Key::KeyC),
+                ]),
+                Remap::Replace(vec![
+                    EventType::KeyRelease(Key::KeyC),
+                    EventType::KeyRelease(Key::ControlLeft),
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_alt_gr_is_ctrl_alt() {
+        let mut remapper = Remapper::new(keymap(&[("Ctrl+Alt+E", "F1"), ("Ctrl+Alt+X", "Alt+Y")]));
+        assert_eq!(
+            run(
+                &mut remapper,
+                &[
+                    EventType::KeyPress(Key::AltGr),
+                    EventType::KeyPress(Key::KeyE),
+                    EventType::KeyRelease(Key::KeyE),
+                    EventType::KeyPress(Key::KeyX),
+                    EventType::KeyRelease(Key::KeyX),
+                    EventType::KeyRelease(Key::AltGr),
+                ]
+            ),
+            vec![
+                Remap::Pass,
+                Remap::Replace(vec![
+                    EventType::KeyRelease(Key::AltGr),
+                    EventType::KeyPress(Key::F1),
+                ]),
+                Remap::Replace(vec![
+                    EventType::KeyRelease(Key::F1),
+                    EventType::KeyPress(Key::AltGr),
+                ]),
+                // AltGr also holds Ctrl, so Alt is pressed on its own.
+                Remap::Replace(vec![
+                    EventType::KeyRelease(Key::AltGr),
+                    EventType::KeyPress(Key::Alt),
+                    EventType::KeyPress(Key::KeyY),
+                ]),
+                Remap::Replace(vec![
+                    EventType::KeyRelease(Key::KeyY),
+                    EventType::KeyRelease(Key::Alt),
+                    EventType::KeyPress(Key::AltGr),
+                ]),
+                Remap::Pass,
+            ]
+        );
+
+        let mut remapper = Remapper::new(keymap(&[("Alt+E", "F1")]));
+        assert_eq!(
+            run(
+                &mut remapper,
+                &[
+                    EventType::KeyPress(Key::AltGr),
+                    EventType::KeyPress(Key::KeyE)
+                ]
+            ),
+            vec![Remap::Pass, Remap::Pass]
+        );
+    }
+
+    #[test]
+    fn test_remapped_modifier_source() {
+        let mut remapper = Remapper::new(keymap(&[("CapsLock", "LCtrl"), ("Ctrl+H", "Backspace")]));
+        assert_eq!(
+            run(
+                &mut remapper,
+                &[
+                    EventType::KeyPress(Key::CapsLock),
+                    EventType::KeyPress(Key::KeyH),
+                    EventType::KeyRelease(Key::KeyH),
+                    EventType::KeyRelease(Key::CapsLock),
+                ]
+            ),
+            vec![
+                Remap::Replace(vec![EventType::KeyPress(Key::ControlLeft)]),
+                Remap::Replace(vec![
+                    EventType::KeyRelease(Key::ControlLeft),
+                    EventType::KeyPress(Key::Backspace),
+                ]),
+                Remap::Replace(vec![
+                    EventType::KeyRelease(Key::Backspace),
+                    EventType::KeyPress(Key::ControlLeft),
+                ]),
+                Remap::Replace(vec![EventType::KeyRelease(Key::ControlLeft)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keymap_change_while_held() {
+        let mut remapper = Remapper::new(keymap(&[("CapsLock", "Escape")]));
+        assert_eq!(
+            remapper.process(&EventType::KeyPress(Key::CapsLock)),
+            Remap::Replace(vec![EventType::KeyPress(Key::Escape)])
+        );
+
+        remapper.set_keymap(keymap(&[("CapsLock", "Tab")]));
+        assert_eq!(
+            remapper.process(&EventType::KeyRelease(Key::CapsLock)),
+            Remap::Replace(vec![EventType::KeyRelease(Key::Escape)])
+        );
+        assert_eq!(
+            remapper.process(&EventType::KeyPress(Key::CapsLock)),
+            Remap::Replace(vec![EventType::KeyPress(Key::Tab)])
+        );
+
+        remapper.set_keymap(Keymap::new());
+        assert_eq!(
+            remapper.process(&EventType::KeyRelease(Key::CapsLock)),
+            Remap::Replace(vec![EventType::KeyRelease(Key::Tab)])
+        );
+        assert_eq!(
+            remapper.process(&EventType::KeyPress(Key::CapsLock)),
+            Remap::Pass
+        );
+    }
+
+    #[test]
+    fn test_filter_lets_original_through_when_blocked() {
+        let mut remapper = Remapper::new(keymap(&[("CapsLock", "Ctrl+Escape")]));
+        let blocked = |events: &[EventType]| {
+            Err(BatchError::Blocked {
+                injected: 0,
+                total: events.len(),
+            })
+        };
+        let mut sent = vec![];
+        let mut send = |events: &[EventType]| {
+            sent.push(events.to_vec());
+            Ok(())
+        };
+
+        let press = event(EventType::KeyPress(Key::CapsLock));
+        assert_eq!(remapper.filter_with(press.clone(), blocked), Some(press));
+        // The press went through unmapped, so its release does too.
+        let release = event(EventType::KeyRelease(Key::CapsLock));
+        assert_eq!(
+            remapper.filter_with(release.clone(), &mut send),
+            Some(release)
+        );
+
+        assert_eq!(
+            remapper.filter_with(event(EventType::KeyPress(Key::CapsLock)), &mut send),
+            None
+        );
+        assert_eq!(
+            sent,
+            vec![vec![
+                EventType::KeyPress(Key::ControlLeft),
+                EventType::KeyPress(Key::Escape)
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_filter_undoes_partial_press() {
+        let mut remapper = Remapper::new(keymap(&[("CapsLock", "Ctrl+Escape")]));
+        let mut sent = vec![];
+        let press = event(EventType::KeyPress(Key::CapsLock));
+        assert_eq!(
+            remapper.filter_with(press.clone(), failing_send(&mut sent, 1)),
+            Some(press)
+        );
+        assert_eq!(
+            sent,
+            vec![
+                vec![
+                    EventType::KeyPress(Key::ControlLeft),
+                    EventType::KeyPress(Key::Escape)
+                ],
+                vec![EventType::KeyRelease(Key::ControlLeft)],
+            ]
+        );
+        // Held as itself: repeats and the release go through too.
+        assert_eq!(
+            run(
+                &mut remapper,
+                &[
+                    EventType::KeyPress(Key::CapsLock),
+                    EventType::KeyRelease(Key::CapsLock),
+                ]
+            ),
+            vec![Remap::Pass, Remap::Pass]
+        );
+    }
+
+    #[test]
+    fn test_filter_releases_target_when_blocked() {
+        let mut remapper = Remapper::new(keymap(&[("CapsLock", "Ctrl+Escape")]));
+        assert_eq!(
+            remapper.filter_with(event(EventType::KeyPress(Key::CapsLock)), |_| Ok(())),
+            None
+        );
+
+        let mut sent = vec![];
+        let release = remapper.filter_with(
+            event(EventType::KeyRelease(Key::CapsLock)),
+            failing_send(&mut sent, 1),
+        );
+        assert_eq!(
+            release.map(|event| event.event_type),
+            Some(EventType::KeyRelease(Key::Escape))
+        );
+        assert_eq!(
+            sent,
+            vec![
+                vec![
+                    EventType::KeyRelease(Key::Escape),
+                    EventType::KeyRelease(Key::ControlLeft)
+                ],
+                vec![EventType::KeyPress(Key::Escape)],
+            ]
+        );
+        // Not held anymore, so the next press is remapped again.
+        assert_eq!(
+            remapper.process(&EventType::KeyPress(Key::CapsLock)),
+            Remap::Replace(vec![
+                EventType::KeyPress(Key::ControlLeft),
+                EventType::KeyPress(Key::Escape)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_filter_restores_released_modifiers_when_blocked() {
+        let mut remapper = Remapper::new(keymap(&[("Ctrl+H", "Backspace")]));
+        let mut sent = vec![];
+        remapper.filter_with(event(EventType::KeyPress(Key::ControlLeft)), |_| Ok(()));
+        let press = event(EventType::KeyPress(Key::KeyH));
+        assert_eq!(
+            remapper.filter_with(press.clone(), failing_send(&mut sent, 0)),
+            Some(press)
+        );
+        assert_eq!(sent.len(), 1);
+        // Control is still held and H went through as itself.
+        assert_eq!(
+            run(
+                &mut remapper,
+                &[
+                    EventType::KeyRelease(Key::KeyH),
+                    EventType::KeyPress(Key::KeyH),
+                ]
+            ),
+            vec![
+                Remap::Pass,
+                Remap::Replace(vec![
+                    EventType::KeyRelease(Key::ControlLeft),
+                    EventType::KeyPress(Key::Backspace),
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keymap_insert_remove() {
+        let mut keymap = keymap(&[("CapsLock", "Escape")]);
+        let caps = "CapsLock".parse().unwrap();
+        assert_eq!(
+            keymap.insert(caps, "Tab".parse().unwrap()),
+            Some("Escape".parse().unwrap())
+        );
+        assert_eq!(keymap.len(), 1);
+        assert_eq!(keymap.remove(&caps), Some("Tab".parse().unwrap()));
+        assert!(keymap.is_empty());
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_keymap_from_config() {
+        let expected = keymap(&[("CapsLock", "Escape"), ("Ctrl+H", "Backspace")]);
+        let toml = "CapsLock = \"Escape\"\n\"Ctrl+H\" = \"Backspace\"\n";
+        assert_eq!(Keymap::from_toml(toml).unwrap(), expected);
+        let json = r#"{"CapsLock": "Escape", "Ctrl+H": "Backspace"}"#;
+        assert_eq!(Keymap::from_json(json).unwrap(), expected);
+
+        assert!(matches!(
+            Keymap::from_toml("CapsLock = \"Hyper\""),
+            Err(KeymapError::Hotkey { .. })
+        ));
+        assert!(matches!(Keymap::from_json("[]"), Err(KeymapError::Json(_))));
+    }
+}
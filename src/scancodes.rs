@@ -0,0 +1,244 @@
+use crate::win_binder::Key;
+use crate::win_binder::ScanCode;
+
+macro_rules! decl_scancodes {
+    ($($key:ident, $code:literal, $extended:literal),*) => {
+        /// Every key with a scan code.
+        #[cfg(test)]
+        const KEYS: &[Key] = &[$(Key::$key),*];
+
+        fn scan_code_from_key(key: Key) -> Option<ScanCode> {
+            match key {
+                $(
+                    Key::$key => Some(ScanCode::new($code, $extended)),
+                )*
+                _ => None,
+            }
+        }
+
+        fn key_from_scan_code(scan_code: ScanCode) -> Option<Key> {
+            match (scan_code.code, scan_code.extended) {
+                $(
+                    ($code, $extended) => Some(Key::$key),
+                )*
+                _ => None,
+            }
+        }
+    };
+}
+
+// Pause really sends E1 1D 45, which Windows reports as 45 without the
+// extended flag, while Num Lock is reported as E0 45.
+decl_scancodes! {
+    Escape, 0x01, false,
+    Num1, 0x02, false,
+    Num2, 0x03, false,
+    Num3, 0x04, false,
+    Num4, 0x05, false,
+    Num5, 0x06, false,
+    Num6, 0x07, false,
+    Num7, 0x08, false,
+    Num8, 0x09, false,
+    Num9, 0x0A, false,
+    Num0, 0x0B, false,
+    Minus, 0x0C, false,
+    Equal, 0x0D, false,
+    Backspace, 0x0E, false,
+    Tab, 0x0F, false,
+    KeyQ, 0x10, false,
+    KeyW, 0x11, false,
+    KeyE, 0x12, false,
+    KeyR, 0x13, false,
+    KeyT, 0x14, false,
+    KeyY, 0x15, false,
+    KeyU, 0x16, false,
+    KeyI, 0x17, false,
+    KeyO, 0x18, false,
+    KeyP, 0x19, false,
+    LeftBracket, 0x1A, false,
+    RightBracket, 0x1B, false,
+    Return, 0x1C, false,
+    ControlLeft, 0x1D, false,
+    KeyA, 0x1E, false,
+    KeyS, 0x1F, false,
+    KeyD, 0x20, false,
+    KeyF, 0x21, false,
+    KeyG, 0x22, false,
+    KeyH, 0x23, false,
+    KeyJ, 0x24, false,
+    KeyK, 0x25, false,
+    KeyL, 0x26, false,
+    SemiColon, 0x27, false,
+    Quote, 0x28, false,
+    BackQuote, 0x29, false,
+    ShiftLeft, 0x2A, false,
+    BackSlash, 0x2B, false,
+    KeyZ, 0x2C, false,
+    KeyX, 0x2D, false,
+    KeyC, 0x2E, false,
+    KeyV, 0x2F, false,
+    KeyB, 0x30, false,
+    KeyN, 0x31, false,
+    KeyM, 0x32, false,
+    Comma, 0x33, false,
+    Dot, 0x34, false,
+    Slash, 0x35, false,
+    ShiftRight, 0x36, false,
+    KpMultiply, 0x37, false,
+    Alt, 0x38, false,
+    Space, 0x39, false,
+    CapsLock, 0x3A, false,
+    F1, 0x3B, false,
+    F2, 0x3C, false,
+    F3, 0x3D, false,
+    F4, 0x3E, false,
+    F5, 0x3F, false,
+    F6, 0x40, false,
+    F7, 0x41, false,
+    F8, 0x42, false,
+    F9, 0x43, false,
+    F10, 0x44, false,
+    Pause, 0x45, false,
+    ScrollLock, 0x46, false,
+    Kp7, 0x47, false,
+    Kp8, 0x48, false,
+    Kp9, 0x49, false,
+    KpMinus, 0x4A, false,
+    Kp4, 0x4B, false,
+    Kp5, 0x4C, false,
+    Kp6, 0x4D, false,
+    KpPlus, 0x4E, false,
+    Kp1, 0x4F, false,
+    Kp2, 0x50, false,
+    Kp3, 0x51, false,
+    Kp0, 0x52, false,
+    KpDelete, 0x53, false,
+    IntlBackslash, 0x56, false,
+    F11, 0x57, false,
+    F12, 0x58, false,
+    F13, 0x64, false,
+    F14, 0x65, false,
+    F15, 0x66, false,
+    F16, 0x67, false,
+    F17, 0x68, false,
+    F18, 0x69, false,
+    F19, 0x6A, false,
+    F20, 0x6B, false,
+    F21, 0x6C, false,
+    F22, 0x6D, false,
+    F23, 0x6E, false,
+    Kana, 0x70, false,
+    F24, 0x76, false,
+    Convert, 0x79, false,
+    NonConvert, 0x7B, false,
+    KpSeparator, 0x7E, false,
+    MediaPrevTrack, 0x10, true,
+    MediaNextTrack, 0x19, true,
+    KpReturn, 0x1C, true,
+    ControlRight, 0x1D, true,
+    VolumeMute, 0x20, true,
+    LaunchApp2, 0x21, true,
+    MediaPlayPause, 0x22, true,
+    MediaStop, 0x24, true,
+    VolumeDown, 0x2E, true,
+    VolumeUp, 0x30, true,
+    BrowserHome, 0x32, true,
+    KpDivide, 0x35, true,
+    PrintScreen, 0x37, true,
+    AltGr, 0x38, true,
+    NumLock, 0x45, true,
+    Home, 0x47, true,
+    UpArrow, 0x48, true,
+    PageUp, 0x49, true,
+    LeftArrow, 0x4B, true,
+    RightArrow, 0x4D, true,
+    End, 0x4F, true,
+    DownArrow, 0x50, true,
+    PageDown, 0x51, true,
+    Insert, 0x52, true,
+    Delete, 0x53, true,
+    MetaLeft, 0x5B, true,
+    MetaRight, 0x5C, true,
+    ContextMenu, 0x5D, true,
+    Sleep, 0x5F, true,
+    BrowserSearch, 0x65, true,
+    BrowserFavorites, 0x66, true,
+    BrowserRefresh, 0x67, true,
+    BrowserStop, 0x68, true,
+    BrowserForward, 0x69, true,
+    BrowserBack, 0x6A, true,
+    LaunchApp1, 0x6B, true,
+    LaunchMail, 0x6C, true,
+    LaunchMediaSelect, 0x6D, true
+}
+
+impl Key {
+    /// The key at the physical position of `scan_code`, ignoring the layout:
+    /// on an AZERTY keyboard the key labelled A is `KeyQ`.
+    pub fn from_scan_code(scan_code: ScanCode) -> Option<Key> {
+        key_from_scan_code(scan_code)
+    }
+
+    /// The scan code of the key in the US layout, `None` for keys that don't
+    /// have a standard one.
+    pub fn to_scan_code(self) -> Option<ScanCode> {
+        scan_code_from_key(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_keys_round_trip() {
+        let mut seen = HashSet::new();
+        for key in KEYS {
+            let scan_code = key.to_scan_code().unwrap();
+            assert!(seen.insert(scan_code), "{:?} is used twice", scan_code);
+            assert_eq!(Key::from_scan_code(scan_code), Some(*key));
+        }
+    }
+
+    #[test]
+    fn test_scan_codes_round_trip() {
+        for code in 0..=0xFF {
+            for extended in [false, true] {
+                let scan_code = ScanCode::new(code, extended);
+                if let Some(key) = Key::from_scan_code(scan_code) {
+                    assert_eq!(key.to_scan_code(), Some(scan_code));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_extended_keys() {
+        assert_eq!(
+            Key::from_scan_code(ScanCode::new(0x1C, false)),
+            Some(Key::Return)
+        );
+        assert_eq!(
+            Key::from_scan_code(ScanCode::new(0x1C, true)),
+            Some(Key::KpReturn)
+        );
+        assert_eq!(
+            Key::from_scan_code(ScanCode::new(0x48, false)),
+            Some(Key::Kp8)
+        );
+        assert_eq!(
+            Key::from_scan_code(ScanCode::new(0x48, true)),
+            Some(Key::UpArrow)
+        );
+        assert_eq!(Key::AltGr.to_scan_code(), Some(ScanCode::new(0x38, true)));
+    }
+
+    #[test]
+    fn test_unmapped() {
+        assert_eq!(Key::from_scan_code(ScanCode::new(0x00, false)), None);
+        assert_eq!(Key::from_scan_code(ScanCode::new(0x01, true)), None);
+        assert_eq!(Key::Function.to_scan_code(), None);
+        assert_eq!(Key::Unknown(0x1E).to_scan_code(), None);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::win_binder::EventType;
+use crate::win_binder::WheelUnit;
+
+/// What a notch of a regular wheel is worth in raw units, `WHEEL_DELTA`.
+pub const NOTCH: i64 = 120;
+
+impl WheelUnit {
+    /// `delta` in raw units, as sent to `SendInput`. `None` if it doesn't
+    /// fit.
+    pub fn to_raw(self, delta: i64) -> Option<i32> {
+        let raw = match self {
+            WheelUnit::Notches => delta.checked_mul(NOTCH)?,
+            WheelUnit::Raw => delta,
+        };
+        i32::try_from(raw).ok()
+    }
+}
+
+/// How far a notch of the wheel scrolls, as set in the mouse settings of
+/// Windows.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ScrollStep {
+    Lines(u32),
+    Page,
+}
+
+impl Default for ScrollStep {
+    /// The default of Windows.
+    fn default() -> Self {
+        ScrollStep::Lines(3)
+    }
+}
+
+/// Whole lines or pages to scroll, positive up or right.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Scroll {
+    Lines { x: i64, y: i64 },
+    Pages { x: i64, y: i64 },
+}
+
+/// Adds up wheel events, including the fractions of a notch sent by
+/// precision touchpads and free spinning wheels, and returns the whole lines
+/// or pages to scroll. What is left over carries on to the next events.
+///
+/// ```
+/// use win_binder::{EventType, Scroll, ScrollAccumulator, ScrollStep, WheelUnit};
+///
+/// let mut accumulator = ScrollAccumulator::new(ScrollStep::Lines(3));
+/// let wheel = |delta_y| EventType::Wheel { delta_x: 0, delta_y, unit: WheelUnit::Raw };
+/// assert_eq!(accumulator.add(&wheel(30)), None);
+/// assert_eq!(accumulator.add(&wheel(30)), Some(Scroll::Lines { x: 0, y: 1 }));
+/// ```
+#[derive(Debug, Default)]
+pub struct ScrollAccumulator {
+    step: ScrollStep,
+    /// In raw units times the lines per notch, a whole line being `NOTCH`.
+    pending_x: i64,
+    pending_y: i64,
+}
+
+impl ScrollAccumulator {
+    pub fn new(step: ScrollStep) -> ScrollAccumulator {
+        ScrollAccumulator {
+            step,
+            pending_x: 0,
+            pending_y: 0,
+        }
+    }
+
+    /// `None` for other events and while less than a line or page was
+    /// scrolled in both directions.
+    pub fn add(&mut self, event_type: &EventType) -> Option<Scroll> {
+        let EventType::Wheel {
+            delta_x,
+            delta_y,
+            unit,
+        } = *event_type
+        else {
+            return None;
+        };
+        let per_notch = match self.step {
+            ScrollStep::Lines(lines) => i64::from(lines),
+            ScrollStep::Page => 1,
+        };
+        let raw = |delta: i64| match unit {
+            WheelUnit::Notches => delta.saturating_mul(NOTCH),
+            WheelUnit::Raw => delta,
+        };
+        // Turning back drops what was left in the other direction.
+        for (pending, delta) in [
+            (&mut self.pending_x, delta_x),
+            (&mut self.pending_y, delta_y),
+        ] {
+            let delta = raw(delta).saturating_mul(per_notch);
+            if delta.signum() == -pending.signum() {
+                *pending = 0;
+            }
+            *pending = pending.saturating_add(delta);
+        }
+        let x = self.pending_x / NOTCH;
+        let y = self.pending_y / NOTCH;
+        if x == 0 && y == 0 {
+            return None;
+        }
+        self.pending_x -= x * NOTCH;
+        self.pending_y -= y * NOTCH;
+        Some(match self.step {
+            ScrollStep::Lines(_) => Scroll::Lines { x, y },
+            ScrollStep::Page => Scroll::Pages { x, y },
+        })
+    }
+
+    /// Drops the fractions of a line or page left over.
+    pub fn reset(&mut self) {
+        self.pending_x = 0;
+        self.pending_y = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wheel(delta_x: i64, delta_y: i64, unit: WheelUnit) -> EventType {
+        EventType::Wheel {
+            delta_x,
+            delta_y,
+            unit,
+        }
+    }
+
+    #[test]
+    fn test_to_raw() {
+        assert_eq!(WheelUnit::Notches.to_raw(-2), Some(-240));
+        assert_eq!(WheelUnit::Raw.to_raw(15), Some(15));
+        // Used to overflow an i16.
+        assert_eq!(WheelUnit::Notches.to_raw(300), Some(36000));
+        assert_eq!(WheelUnit::Notches.to_raw(i64::MAX), None);
+        assert_eq!(WheelUnit::Raw.to_raw(i64::from(i32::MIN) - 1), None);
+    }
+
+    #[test]
+    fn test_fractions_add_up() {
+        let mut accumulator = ScrollAccumulator::new(ScrollStep::Lines(3));
+        // 120 / 3 = 40 raw units per line.
+        assert_eq!(accumulator.add(&wheel(0, 15, WheelUnit::Raw)), None);
+        assert_eq!(accumulator.add(&wheel(0, 15, WheelUnit::Raw)), None);
+        assert_eq!(
+            accumulator.add(&wheel(0, 15, WheelUnit::Raw)),
+            Some(Scroll::Lines { x: 0, y: 1 })
+        );
+        // 5 left over.
+        assert_eq!(
+            accumulator.add(&wheel(0, 75, WheelUnit::Raw)),
+            Some(Scroll::Lines { x: 0, y: 2 })
+        );
+        assert_eq!(
+            accumulator.add(&wheel(0, 1, WheelUnit::Notches)),
+            Some(Scroll::Lines { x: 0, y: 3 })
+        );
+        assert_eq!(
+            accumulator.add(&EventType::MouseMove { x: 0.0, y: 0.0 }),
+            None
+        );
+    }
+
+    #[test]
+    fn test_directions() {
+        let mut accumulator = ScrollAccumulator::new(ScrollStep::Lines(1));
+        assert_eq!(accumulator.add(&wheel(100, -100, WheelUnit::Raw)), None);
+        // Turning back starts over, going on completes the line.
+        assert_eq!(
+            accumulator.add(&wheel(-100, -20, WheelUnit::Raw)),
+            Some(Scroll::Lines { x: 0, y: -1 })
+        );
+        assert_eq!(
+            accumulator.add(&wheel(-20, 0, WheelUnit::Raw)),
+            Some(Scroll::Lines { x: -1, y: 0 })
+        );
+        accumulator.add(&wheel(0, 100, WheelUnit::Raw));
+        accumulator.reset();
+        assert_eq!(accumulator.add(&wheel(0, 20, WheelUnit::Raw)), None);
+    }
+
+    #[test]
+    fn test_pages() {
+        let mut accumulator = ScrollAccumulator::new(ScrollStep::Page);
+        assert_eq!(accumulator.add(&wheel(0, -60, WheelUnit::Raw)), None);
+        assert_eq!(
+            accumulator.add(&wheel(0, -3, WheelUnit::Notches)),
+            Some(Scroll::Pages { x: 0, y: -3 })
+        );
+        assert_eq!(
+            accumulator.add(&wheel(0, -60, WheelUnit::Raw)),
+            Some(Scroll::Pages { x: 0, y: -1 })
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use windows::Win32::Foundation::GetLastError;
 use windows::Win32::UI::Input::KeyboardAndMouse::SendInput;
 use windows::Win32::UI::Input::KeyboardAndMouse::INPUT;
 use windows::Win32::UI::Input::KeyboardAndMouse::INPUT_0;
@@ -5,7 +6,10 @@ use windows::Win32::UI::Input::KeyboardAndMouse::INPUT_KEYBOARD;
 use windows::Win32::UI::Input::KeyboardAndMouse::INPUT_MOUSE;
 use windows::Win32::UI::Input::KeyboardAndMouse::KEYBDINPUT;
 use windows::Win32::UI::Input::KeyboardAndMouse::KEYBD_EVENT_FLAGS;
+use windows::Win32::UI::Input::KeyboardAndMouse::KEYEVENTF_EXTENDEDKEY;
 use windows::Win32::UI::Input::KeyboardAndMouse::KEYEVENTF_KEYUP;
+use windows::Win32::UI::Input::KeyboardAndMouse::KEYEVENTF_SCANCODE;
+use windows::Win32::UI::Input::KeyboardAndMouse::KEYEVENTF_UNICODE;
 use windows::Win32::UI::Input::KeyboardAndMouse::MOUSEEVENTF_ABSOLUTE;
 use windows::Win32::UI::Input::KeyboardAndMouse::MOUSEEVENTF_HWHEEL;
 use windows::Win32::UI::Input::KeyboardAndMouse::MOUSEEVENTF_LEFTDOWN;
@@ -22,31 +26,51 @@ use windows::Win32::UI::Input::KeyboardAndMouse::MOUSEEVENTF_XUP;
 use windows::Win32::UI::Input::KeyboardAndMouse::MOUSEINPUT;
 use windows::Win32::UI::Input::KeyboardAndMouse::MOUSE_EVENT_FLAGS;
 use windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY;
-use windows::Win32::UI::WindowsAndMessaging::GetSystemMetrics;
-use windows::Win32::UI::WindowsAndMessaging::SM_CXVIRTUALSCREEN;
-use windows::Win32::UI::WindowsAndMessaging::SM_CYVIRTUALSCREEN;
-use windows::Win32::UI::WindowsAndMessaging::WHEEL_DELTA;
 
 use crate::common::DWORD;
 use crate::common::LONG;
 use crate::common::WORD;
+use crate::coordinates::to_absolute;
+use crate::display::virtual_screen_bounds;
 use crate::keycodes::code_from_key;
+use crate::win_binder::BatchError;
 use crate::win_binder::Button;
 use crate::win_binder::EventType;
+use crate::win_binder::Key;
 use crate::win_binder::SimulateError;
 use std::convert::TryFrom;
 use std::mem::size_of;
 use std::os::raw::c_int;
-use std::os::raw::c_short;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 
 static KEYEVENTF_KEYDOWN: KEYBD_EVENT_FLAGS = KEYBD_EVENT_FLAGS(0);
 
-fn sim_mouse_event(
+/// "WINB"
+static INJECTION_SIGNATURE: AtomicUsize = AtomicUsize::new(0x5749_4E42);
+
+/// Sets the value written into `dwExtraInfo` of the events simulated by this
+/// process, which is how its listeners tell them apart from the others (see
+/// `EventOrigin`). Events injected in place of the ones changed by a grabber
+/// carry `signature + 1`.
+///
+/// Only needed if another program uses the default signature, or to
+/// recognize the events of another program using this crate.
+pub fn set_injection_signature(signature: usize) {
+    INJECTION_SIGNATURE.store(signature, Ordering::Relaxed);
+}
+
+pub fn injection_signature() -> usize {
+    INJECTION_SIGNATURE.load(Ordering::Relaxed)
+}
+
+fn mouse_input(
     flags: MOUSE_EVENT_FLAGS,
     data: DWORD,
     dx: LONG,
     dy: LONG,
-) -> Result<(), SimulateError> {
+    extra_info: usize,
+) -> INPUT {
     let mut union: INPUT_0 = unsafe { std::mem::zeroed() };
     union.mi = MOUSEINPUT {
         dx,
@@ -54,102 +78,485 @@ fn sim_mouse_event(
         mouseData: data,
         dwFlags: flags,
         time: 0,
-        dwExtraInfo: 0,
+        dwExtraInfo: extra_info,
     };
-    let input = [INPUT {
+    INPUT {
         r#type: INPUT_MOUSE,
         Anonymous: union,
-    }; 1];
-    let value = unsafe { SendInput(&input, size_of::<INPUT>() as c_int) };
-    if value != 1 {
-        Err(SimulateError)
-    } else {
-        Ok(())
     }
 }
 
-fn sim_keyboard_event(
+fn keyboard_input(
     flags: KEYBD_EVENT_FLAGS,
     vk: VIRTUAL_KEY,
     scan: WORD,
-) -> Result<(), SimulateError> {
+    extra_info: usize,
+) -> INPUT {
     let mut union: INPUT_0 = unsafe { std::mem::zeroed() };
     union.ki = KEYBDINPUT {
         wVk: vk,
         wScan: scan,
         dwFlags: flags,
         time: 0,
-        dwExtraInfo: 0,
+        dwExtraInfo: extra_info,
     };
-    let input = [INPUT {
+    INPUT {
         r#type: INPUT_KEYBOARD,
         Anonymous: union,
-    }; 1];
-    let value = unsafe { SendInput(&input, size_of::<INPUT>() as c_int) };
-    if value != 1 {
-        Err(SimulateError)
+    }
+}
+
+/// Sends all `inputs` in one `SendInput` call and returns how many the OS
+/// actually injected.
+fn send_inputs(inputs: &[INPUT]) -> usize {
+    if inputs.is_empty() {
+        return 0;
+    }
+    unsafe { SendInput(inputs, size_of::<INPUT>() as c_int) as usize }
+}
+
+fn send_all(inputs: &[INPUT], event_type: Option<&EventType>) -> Result<(), SimulateError> {
+    if send_inputs(inputs) != inputs.len() {
+        let os_error = unsafe { GetLastError() };
+        Err(SimulateError::Blocked {
+            event_type: event_type.copied(),
+            os_error: os_error.0,
+        })
     } else {
         Ok(())
     }
 }
 
+fn unicode_input(unit: u16, flags: KEYBD_EVENT_FLAGS, extra_info: usize) -> INPUT {
+    let mut union: INPUT_0 = unsafe { std::mem::zeroed() };
+    union.ki = KEYBDINPUT {
+        wVk: VIRTUAL_KEY(0),
+        wScan: unit,
+        dwFlags: KEYEVENTF_UNICODE | flags,
+        time: 0,
+        dwExtraInfo: extra_info,
+    };
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: union,
+    }
+}
+
+/// A down/up pair of KEYEVENTF_UNICODE inputs for every UTF-16 unit of `text`,
+/// so characters outside the BMP are sent as their two surrogates.
+fn text_inputs(text: &str, extra_info: usize) -> Vec<INPUT> {
+    text.encode_utf16()
+        .flat_map(|unit| {
+            [
+                unicode_input(unit, KEYEVENTF_KEYDOWN, extra_info),
+                unicode_input(unit, KEYEVENTF_KEYUP, extra_info),
+            ]
+        })
+        .collect()
+}
+
+/// Types `text` with KEYEVENTF_UNICODE events, whatever the keyboard layout,
+/// in a single `SendInput` call so it can't be interleaved with user input.
+/// Applications receive the characters, not key presses: shortcuts won't
+/// trigger and some applications ignore such input.
+///
+/// ```no_run
+/// use win_binder::simulate_text;
+///
+/// simulate_text("Héllo wörld 👋").unwrap();
+/// ```
+pub fn simulate_text(text: &str) -> Result<(), SimulateError> {
+    send_all(&text_inputs(text, injection_signature()), None)
+}
+
+/// How `simulate_with` sends events.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct SimulateOptions {
+    /// Send keys by scan code (`KEYEVENTF_SCANCODE`) rather than by virtual
+    /// key, for the applications that read scan codes, like games and remote
+    /// desktop clients. The key then means its US layout position: `KeyQ` types
+    /// an A with an AZERTY layout. Keys without a scan code are still sent by
+    /// virtual key.
+    pub use_scan_codes: bool,
+}
+
 pub fn simulate(event_type: &EventType) -> Result<(), SimulateError> {
-    match event_type {
-        EventType::KeyPress(key) => {
-            let code = code_from_key(*key).ok_or(SimulateError)?;
-            sim_keyboard_event(KEYEVENTF_KEYDOWN, VIRTUAL_KEY(code), 0)
-        }
-        EventType::KeyRelease(key) => {
-            let code = code_from_key(*key).ok_or(SimulateError)?;
-            sim_keyboard_event(KEYEVENTF_KEYUP, VIRTUAL_KEY(code), 0)
+    simulate_with(event_type, SimulateOptions::default())
+}
+
+/// ```no_run
+/// use win_binder::{simulate_with, EventType, Key, SimulateOptions};
+///
+/// let options = SimulateOptions {
+///     use_scan_codes: true,
+/// };
+/// simulate_with(&EventType::KeyPress(Key::KeyW), options).unwrap();
+/// simulate_with(&EventType::KeyRelease(Key::KeyW), options).unwrap();
+/// ```
+pub fn simulate_with(
+    event_type: &EventType,
+    options: SimulateOptions,
+) -> Result<(), SimulateError> {
+    simulate_with_extra_info(event_type, injection_signature(), options)
+}
+
+/// Simulates a replacement event, which our own hooks let through without
+/// handing it to grabbers again.
+pub(crate) fn simulate_injected(event_type: &EventType) -> Result<(), SimulateError> {
+    simulate_with_extra_info(
+        event_type,
+        injection_signature().wrapping_add(1),
+        SimulateOptions::default(),
+    )
+}
+
+fn simulate_with_extra_info(
+    event_type: &EventType,
+    extra_info: usize,
+    options: SimulateOptions,
+) -> Result<(), SimulateError> {
+    let mut inputs = vec![];
+    push_inputs(&mut inputs, event_type, extra_info, options)?;
+    send_all(&inputs, Some(event_type))
+}
+
+/// Sends all `events` in a single `SendInput` call, so they reach the system
+/// one after the other without any user input in between, unlike consecutive
+/// calls to `simulate`.
+///
+/// If one of the events can't be simulated, nothing is sent.
+///
+/// ```no_run
+/// use win_binder::{simulate_batch, EventType, Key};
+///
+/// // Ctrl+C
+/// simulate_batch(&[
+///     EventType::KeyPress(Key::ControlLeft),
+///     EventType::KeyPress(Key::KeyC),
+///     EventType::KeyRelease(Key::KeyC),
+///     EventType::KeyRelease(Key::ControlLeft),
+/// ])
+/// .unwrap();
+/// ```
+pub fn simulate_batch(events: &[EventType]) -> Result<(), BatchError> {
+    send_batch(events, injection_signature())
+}
+
+/// Simulates the replacements of a grabbed event at once, which our own
+/// hooks let through without handing them to grabbers again.
+pub(crate) fn simulate_injected_batch(events: &[EventType]) -> Result<(), BatchError> {
+    send_batch(events, injection_signature().wrapping_add(1))
+}
+
+fn send_batch(events: &[EventType], extra_info: usize) -> Result<(), BatchError> {
+    let inputs = batch_inputs(events, extra_info)?;
+    let injected = send_inputs(&inputs);
+    if injected != inputs.len() {
+        return Err(BatchError::Blocked {
+            injected,
+            total: inputs.len(),
+        });
+    }
+    Ok(())
+}
+
+/// The inputs of all `events`, or the first one that can't be simulated.
+fn batch_inputs(events: &[EventType], extra_info: usize) -> Result<Vec<INPUT>, BatchError> {
+    let mut inputs = vec![];
+    for (index, event_type) in events.iter().enumerate() {
+        push_inputs(
+            &mut inputs,
+            event_type,
+            extra_info,
+            SimulateOptions::default(),
+        )
+        .map_err(|error| BatchError::InvalidEvent { index, error })?;
+    }
+    Ok(inputs)
+}
+
+fn key_input(
+    key: Key,
+    flags: KEYBD_EVENT_FLAGS,
+    extra_info: usize,
+    options: SimulateOptions,
+) -> Option<INPUT> {
+    if options.use_scan_codes {
+        if let Some(scan_code) = key.to_scan_code() {
+            let mut flags = flags | KEYEVENTF_SCANCODE;
+            if scan_code.extended {
+                flags |= KEYEVENTF_EXTENDEDKEY;
+            }
+            return Some(keyboard_input(
+                flags,
+                VIRTUAL_KEY(0),
+                scan_code.code,
+                extra_info,
+            ));
         }
-        EventType::ButtonPress(button) => match button {
-            Button::Left => sim_mouse_event(MOUSEEVENTF_LEFTDOWN, 0, 0, 0),
-            Button::Middle => sim_mouse_event(MOUSEEVENTF_MIDDLEDOWN, 0, 0, 0),
-            Button::Right => sim_mouse_event(MOUSEEVENTF_RIGHTDOWN, 0, 0, 0),
-            Button::Unknown(code) => sim_mouse_event(MOUSEEVENTF_XDOWN, (*code).into(), 0, 0),
-        },
-        EventType::ButtonRelease(button) => match button {
-            Button::Left => sim_mouse_event(MOUSEEVENTF_LEFTUP, 0, 0, 0),
-            Button::Middle => sim_mouse_event(MOUSEEVENTF_MIDDLEUP, 0, 0, 0),
-            Button::Right => sim_mouse_event(MOUSEEVENTF_RIGHTUP, 0, 0, 0),
-            Button::Unknown(code) => sim_mouse_event(MOUSEEVENTF_XUP, (*code).into(), 0, 0),
-        },
-        EventType::Wheel { delta_x, delta_y } => {
+    }
+    let code = code_from_key(key)?;
+    // The numpad Enter only differs from Enter by the extended flag.
+    let flags = match key {
+        Key::KpReturn => flags | KEYEVENTF_EXTENDEDKEY,
+        _ => flags,
+    };
+    Some(keyboard_input(flags, VIRTUAL_KEY(code), 0, extra_info))
+}
+
+/// Appends the inputs simulating `event_type`: usually one, but a wheel event
+/// scrolling both ways takes two, and one with no delta none.
+fn push_inputs(
+    inputs: &mut Vec<INPUT>,
+    event_type: &EventType,
+    extra_info: usize,
+    options: SimulateOptions,
+) -> Result<(), SimulateError> {
+    let event_type = *event_type;
+    let out_of_range = || SimulateError::OutOfRange { event_type };
+    match &event_type {
+        EventType::KeyPress(key) => inputs.push(
+            key_input(*key, KEYEVENTF_KEYDOWN, extra_info, options)
+                .ok_or(SimulateError::UnmappedKey { event_type })?,
+        ),
+        EventType::KeyRelease(key) => inputs.push(
+            key_input(*key, KEYEVENTF_KEYUP, extra_info, options)
+                .ok_or(SimulateError::UnmappedKey { event_type })?,
+        ),
+        EventType::ButtonPress(button) => inputs.push(match button {
+            Button::Left => mouse_input(MOUSEEVENTF_LEFTDOWN, 0, 0, 0, extra_info),
+            Button::Middle => mouse_input(MOUSEEVENTF_MIDDLEDOWN, 0, 0, 0, extra_info),
+            Button::Right => mouse_input(MOUSEEVENTF_RIGHTDOWN, 0, 0, 0, extra_info),
+            Button::Back | Button::Forward | Button::Unknown(_) => {
+                let code = button
+                    .x_button()
+                    .ok_or(SimulateError::UnmappedButton { event_type })?;
+                mouse_input(MOUSEEVENTF_XDOWN, code.into(), 0, 0, extra_info)
+            }
+        }),
+        EventType::ButtonRelease(button) => inputs.push(match button {
+            Button::Left => mouse_input(MOUSEEVENTF_LEFTUP, 0, 0, 0, extra_info),
+            Button::Middle => mouse_input(MOUSEEVENTF_MIDDLEUP, 0, 0, 0, extra_info),
+            Button::Right => mouse_input(MOUSEEVENTF_RIGHTUP, 0, 0, 0, extra_info),
+            Button::Back | Button::Forward | Button::Unknown(_) => {
+                let code = button
+                    .x_button()
+                    .ok_or(SimulateError::UnmappedButton { event_type })?;
+                mouse_input(MOUSEEVENTF_XUP, code.into(), 0, 0, extra_info)
+            }
+        }),
+        EventType::Wheel {
+            delta_x,
+            delta_y,
+            unit,
+        } => {
             if *delta_x != 0 {
-                sim_mouse_event(
+                inputs.push(mouse_input(
                     MOUSEEVENTF_HWHEEL,
-                    (c_short::try_from(*delta_x).map_err(|_| SimulateError)? * WHEEL_DELTA as i16)
-                        as u32,
+                    unit.to_raw(*delta_x).ok_or_else(out_of_range)? as u32,
                     0,
                     0,
-                )?;
+                    extra_info,
+                ));
             }
 
             if *delta_y != 0 {
-                sim_mouse_event(
+                inputs.push(mouse_input(
                     MOUSEEVENTF_WHEEL,
-                    (c_short::try_from(*delta_y).map_err(|_| SimulateError)? * WHEEL_DELTA as i16)
-                        as u32,
+                    unit.to_raw(*delta_y).ok_or_else(out_of_range)? as u32,
                     0,
                     0,
-                )?;
+                    extra_info,
+                ));
             }
-            Ok(())
         }
         EventType::MouseMove { x, y } => {
-            let width = unsafe { GetSystemMetrics(SM_CXVIRTUALSCREEN) };
-            let height = unsafe { GetSystemMetrics(SM_CYVIRTUALSCREEN) };
-            if width == 0 || height == 0 {
-                return Err(SimulateError);
-            }
+            let no_display = || SimulateError::NoDisplay { event_type };
+            let screen = virtual_screen_bounds().map_err(|_| no_display())?;
+            let (dx, dy) = to_absolute(*x, *y, screen).ok_or_else(no_display)?;
 
-            sim_mouse_event(
+            inputs.push(mouse_input(
                 MOUSEEVENTF_MOVE | MOUSEEVENTF_ABSOLUTE | MOUSEEVENTF_VIRTUALDESK,
                 0,
-                (*x as i32 + 1) * 65535 / width,
-                (*y as i32 + 1) * 65535 / height,
-            )
+                dx,
+                dy,
+                extra_info,
+            ));
+        }
+        EventType::MouseMoveRelative { dx, dy } => {
+            inputs.push(mouse_input(
+                MOUSEEVENTF_MOVE,
+                0,
+                LONG::try_from(*dx).map_err(|_| out_of_range())?,
+                LONG::try_from(*dy).map_err(|_| out_of_range())?,
+                extra_info,
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::win_binder::WheelUnit;
+
+    fn units(inputs: &[INPUT]) -> Vec<(u16, bool)> {
+        inputs
+            .iter()
+            .map(|input| {
+                assert_eq!(input.r#type, INPUT_KEYBOARD);
+                let ki = unsafe { input.Anonymous.ki };
+                assert_eq!(ki.wVk, VIRTUAL_KEY(0));
+                assert!(ki.dwFlags.contains(KEYEVENTF_UNICODE));
+                (ki.wScan, ki.dwFlags.contains(KEYEVENTF_KEYUP))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_text_inputs() {
+        assert!(text_inputs("", 0).is_empty());
+        assert_eq!(
+            units(&text_inputs("aé", 0)),
+            vec![(0x61, false), (0x61, true), (0xE9, false), (0xE9, true)]
+        );
+        assert_eq!(
+            units(&text_inputs("中", 0)),
+            vec![(0x4E2D, false), (0x4E2D, true)]
+        );
+    }
+
+    #[test]
+    fn test_text_inputs_surrogate_pairs() {
+        // U+1F44B WAVING HAND SIGN
+        assert_eq!(
+            units(&text_inputs("👋", 0)),
+            vec![
+                (0xD83D, false),
+                (0xD83D, true),
+                (0xDC4B, false),
+                (0xDC4B, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_text_inputs_extra_info() {
+        for input in text_inputs("ab", 42) {
+            assert_eq!(unsafe { input.Anonymous.ki }.dwExtraInfo, 42);
+        }
+    }
+
+    fn keys(inputs: &[INPUT]) -> Vec<(VIRTUAL_KEY, bool)> {
+        inputs
+            .iter()
+            .map(|input| {
+                assert_eq!(input.r#type, INPUT_KEYBOARD);
+                let ki = unsafe { input.Anonymous.ki };
+                assert_eq!(ki.dwExtraInfo, 7);
+                (ki.wVk, ki.dwFlags.contains(KEYEVENTF_KEYUP))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_inputs() {
+        let inputs = batch_inputs(
+            &[
+                EventType::KeyPress(Key::ControlLeft),
+                EventType::KeyPress(Key::KeyC),
+                EventType::KeyRelease(Key::KeyC),
+                EventType::KeyRelease(Key::ControlLeft),
+            ],
+            7,
+        )
+        .unwrap();
+        assert_eq!(
+            keys(&inputs),
+            vec![
+                (VIRTUAL_KEY(162), false),
+                (VIRTUAL_KEY(67), false),
+                (VIRTUAL_KEY(67), true),
+                (VIRTUAL_KEY(162), true)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_batch_inputs_wheel_both_ways() {
+        let inputs = batch_inputs(
+            &[EventType::Wheel {
+                delta_x: -1,
+                delta_y: 2,
+                unit: WheelUnit::Notches,
+            }],
+            7,
+        )
+        .unwrap();
+        let wheels: Vec<_> = inputs
+            .iter()
+            .map(|input| {
+                assert_eq!(input.r#type, INPUT_MOUSE);
+                let mi = unsafe { input.Anonymous.mi };
+                (mi.dwFlags, mi.mouseData as i32)
+            })
+            .collect();
+        assert_eq!(
+            wheels,
+            vec![(MOUSEEVENTF_HWHEEL, -120), (MOUSEEVENTF_WHEEL, 240)]
+        );
+    }
+
+    fn scan_code_input(key: Key) -> KEYBDINPUT {
+        let options = SimulateOptions {
+            use_scan_codes: true,
+        };
+        let input = key_input(key, KEYEVENTF_KEYUP, 7, options).unwrap();
+        assert_eq!(input.r#type, INPUT_KEYBOARD);
+        unsafe { input.Anonymous.ki }
+    }
+
+    #[test]
+    fn test_key_input_scan_codes() {
+        let ki = scan_code_input(Key::KeyA);
+        assert_eq!(ki.wVk, VIRTUAL_KEY(0));
+        assert_eq!(ki.wScan, 0x1E);
+        assert_eq!(ki.dwFlags, KEYEVENTF_SCANCODE | KEYEVENTF_KEYUP);
+
+        // E0 keys
+        let ki = scan_code_input(Key::RightArrow);
+        assert_eq!(ki.wVk, VIRTUAL_KEY(0));
+        assert_eq!(ki.wScan, 0x4D);
+        assert_eq!(
+            ki.dwFlags,
+            KEYEVENTF_SCANCODE | KEYEVENTF_EXTENDEDKEY | KEYEVENTF_KEYUP
+        );
+    }
+
+    #[test]
+    fn test_key_input_scan_codes_fallback() {
+        // No scan code, sent by virtual key.
+        let ki = scan_code_input(Key::Unknown(0x1E));
+        assert_eq!(ki.wVk, VIRTUAL_KEY(0x1E));
+        assert_eq!(ki.wScan, 0);
+        assert_eq!(ki.dwFlags, KEYEVENTF_KEYUP);
+    }
+
+    #[test]
+    fn test_batch_inputs_invalid_event() {
+        let events = [
+            EventType::KeyPress(Key::KeyA),
+            EventType::ButtonPress(Button::Left),
+            EventType::KeyPress(Key::Unknown(0x10000)),
+            EventType::KeyRelease(Key::KeyA),
+        ];
+        match batch_inputs(&events, 7) {
+            Err(BatchError::InvalidEvent { index, error }) => {
+                assert_eq!(index, 2);
+                assert_eq!(error.event_type(), Some(&events[2]));
+            }
+            result => panic!("Unexpected {:?}", result.map(|inputs| inputs.len())),
         }
     }
 }
@@ -0,0 +1,307 @@
+use crate::handle::ListenerHandle;
+use crate::listen::spawn_listen;
+use crate::win_binder::Event;
+use crate::win_binder::ListenError;
+use futures_core::Stream;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+
+/// What the listener does with a new event when the stream buffer is full.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Discard the oldest buffered event to make room.
+    #[default]
+    DropOldest,
+    /// Discard the new event.
+    DropNewest,
+    /// Wait until the stream is polled. This holds up the hook, and with it
+    /// all the input of the system: Windows silently removes hooks that take
+    /// longer than `LowLevelHooksTimeout` to return.
+    Block,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StreamOptions {
+    /// Maximum number of buffered events, at least 1.
+    pub capacity: usize,
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        StreamOptions {
+            capacity: 1024,
+            overflow: OverflowPolicy::default(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Buffer {
+    events: VecDeque<Event>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+#[derive(Default)]
+struct Shared {
+    buffer: Mutex<Buffer>,
+    space: Condvar,
+}
+
+impl Shared {
+    fn push(&self, event: Event, options: StreamOptions) {
+        let Ok(mut buffer) = self.buffer.lock() else {
+            return;
+        };
+        while buffer.events.len() >= options.capacity.max(1) {
+            match options.overflow {
+                OverflowPolicy::DropOldest => {
+                    buffer.events.pop_front();
+                }
+                OverflowPolicy::DropNewest => return,
+                OverflowPolicy::Block => {
+                    if buffer.closed {
+                        return;
+                    }
+                    buffer = match self.space.wait(buffer) {
+                        Ok(buffer) => buffer,
+                        Err(_) => return,
+                    };
+                }
+            }
+        }
+        buffer.events.push_back(event);
+        if let Some(waker) = buffer.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Hands out the next buffered event, or `None` once the buffer is
+    /// closed and empty.
+    fn poll(&self, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        let mut buffer = match self.buffer.lock() {
+            Ok(buffer) => buffer,
+            Err(_) => return Poll::Ready(None),
+        };
+        match buffer.events.pop_front() {
+            Some(event) => {
+                self.space.notify_one();
+                Poll::Ready(Some(event))
+            }
+            None if buffer.closed => Poll::Ready(None),
+            None => {
+                buffer.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Wakes up both ends: a listener blocked on a full buffer gives up,
+    /// and the stream ends once the buffered events are consumed.
+    fn close(&self) {
+        if let Ok(mut buffer) = self.buffer.lock() {
+            buffer.closed = true;
+            if let Some(waker) = buffer.waker.take() {
+                waker.wake();
+            }
+        }
+        self.space.notify_all();
+    }
+}
+
+/// The listener's end of the buffer. The callback owning it is dropped when
+/// the listener thread ends, however it ends, which closes the buffer.
+struct Sender(Arc<Shared>);
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+/// A `Stream` of the events seen by a background listener, as returned by
+/// `listen_stream`. Dropping it unhooks the listener.
+pub struct EventStream {
+    shared: Arc<Shared>,
+    // Stopped after `drop` closed the buffer, so that a listener blocked on a
+    // full buffer can return.
+    _handle: ListenerHandle,
+}
+
+impl Stream for EventStream {
+    type Item = Event;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        self.shared.poll(cx)
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        // Wake up the listener if it is blocked on a full buffer, otherwise
+        // stopping it would wait forever.
+        self.shared.close();
+    }
+}
+
+/// Listens on a background thread and returns the events as a `Stream`,
+/// buffering up to 1024 of them and dropping the oldest ones beyond that.
+///
+/// ```no_run
+/// use std::future::poll_fn;
+/// use std::pin::Pin;
+/// use futures_core::Stream;
+/// use win_binder::listen_stream;
+///
+/// # async fn run() {
+/// let mut events = listen_stream().unwrap();
+/// while let Some(event) = poll_fn(|cx| Pin::new(&mut events).poll_next(cx)).await {
+///     println!("Received {:?}", event);
+/// }
+/// # }
+/// ```
+pub fn listen_stream() -> Result<EventStream, ListenError> {
+    listen_stream_with(StreamOptions::default())
+}
+
+pub fn listen_stream_with(options: StreamOptions) -> Result<EventStream, ListenError> {
+    let shared = Arc::new(Shared::default());
+    let sender = Sender(shared.clone());
+    let handle = spawn_listen(move |event| sender.0.push(event, options))?;
+    Ok(EventStream {
+        shared,
+        _handle: handle,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modifiers::Modifiers;
+    use crate::win_binder::EventType;
+    use crate::win_binder::Key;
+    use std::thread;
+    use std::time::Duration;
+    use std::time::SystemTime;
+
+    fn event(key: Key) -> Event {
+        Event {
+            time: SystemTime::now(),
+            name: None,
+            event_type: EventType::KeyPress(key),
+            modifiers: Modifiers::empty(),
+            raw: None,
+        }
+    }
+
+    fn keys(shared: &Shared) -> Vec<EventType> {
+        let buffer = shared.buffer.lock().unwrap();
+        buffer.events.iter().map(|e| e.event_type).collect()
+    }
+
+    fn options(overflow: OverflowPolicy) -> StreamOptions {
+        StreamOptions {
+            capacity: 2,
+            overflow,
+        }
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        let shared = Shared::default();
+        for key in [Key::KeyA, Key::KeyB, Key::KeyC] {
+            shared.push(event(key), options(OverflowPolicy::DropOldest));
+        }
+        assert_eq!(
+            keys(&shared),
+            vec![
+                EventType::KeyPress(Key::KeyB),
+                EventType::KeyPress(Key::KeyC)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_drop_newest() {
+        let shared = Shared::default();
+        for key in [Key::KeyA, Key::KeyB, Key::KeyC] {
+            shared.push(event(key), options(OverflowPolicy::DropNewest));
+        }
+        assert_eq!(
+            keys(&shared),
+            vec![
+                EventType::KeyPress(Key::KeyA),
+                EventType::KeyPress(Key::KeyB)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_block_until_space() {
+        let shared = Arc::new(Shared::default());
+        shared.push(event(Key::KeyA), options(OverflowPolicy::Block));
+        shared.push(event(Key::KeyB), options(OverflowPolicy::Block));
+
+        let sender = shared.clone();
+        let blocked = thread::spawn(move || {
+            sender.push(event(Key::KeyC), options(OverflowPolicy::Block));
+        });
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(keys(&shared).len(), 2);
+
+        shared.buffer.lock().unwrap().events.pop_front();
+        shared.space.notify_one();
+        blocked.join().unwrap();
+        assert_eq!(
+            keys(&shared),
+            vec![
+                EventType::KeyPress(Key::KeyB),
+                EventType::KeyPress(Key::KeyC)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stream_ends_when_listener_stops() {
+        let shared = Arc::new(Shared::default());
+        let sender = Sender(shared.clone());
+        sender
+            .0
+            .push(event(Key::KeyA), options(OverflowPolicy::DropOldest));
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(matches!(shared.poll(&mut cx), Poll::Ready(Some(_))));
+        assert!(shared.poll(&mut cx).is_pending());
+
+        sender
+            .0
+            .push(event(Key::KeyB), options(OverflowPolicy::DropOldest));
+        drop(sender);
+        // Buffered events are still handed out before the end.
+        assert!(matches!(shared.poll(&mut cx), Poll::Ready(Some(_))));
+        assert!(matches!(shared.poll(&mut cx), Poll::Ready(None)));
+    }
+
+    #[test]
+    fn test_block_gives_up_when_closed() {
+        let shared = Arc::new(Shared::default());
+        shared.push(event(Key::KeyA), options(OverflowPolicy::Block));
+        shared.push(event(Key::KeyB), options(OverflowPolicy::Block));
+
+        let sender = shared.clone();
+        let blocked = thread::spawn(move || {
+            sender.push(event(Key::KeyC), options(OverflowPolicy::Block));
+        });
+        thread::sleep(Duration::from_millis(50));
+        shared.buffer.lock().unwrap().closed = true;
+        shared.space.notify_all();
+        blocked.join().unwrap();
+        assert_eq!(keys(&shared).len(), 2);
+    }
+}
@@ -1,18 +1,53 @@
+use crate::hotkey::HotkeyModifier;
+use crate::modifiers::Modifiers;
 #[cfg(feature = "serialize")]
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fmt::Display;
+#[cfg(feature = "config")]
+use std::path::PathBuf;
 use std::time::SystemTime;
 
 pub type GrabCallback = fn(event: Event) -> Option<Event>;
 
+/// Marking an error when the hooks of a listener could not be installed
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum ListenError {
+    /// `SetWindowsHookEx` failed for the keyboard hook with this
+    /// `GetLastError` code.
     KeyHookError(u32),
+    /// `SetWindowsHookEx` failed for the mouse hook with this `GetLastError`
+    /// code.
     MouseHookError(u32),
+    /// `listen` or `grab` was called from inside a callback, while the hooks
+    /// of the thread dispatch an event. Other calls on a thread that already
+    /// listens share its hooks.
+    Reentrant,
 }
 
+impl Display for ListenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ListenError::KeyHookError(code) => hook_error(f, "keyboard", *code),
+            ListenError::MouseHookError(code) => hook_error(f, "mouse", *code),
+            ListenError::Reentrant => reentrant(f),
+        }
+    }
+}
+
+impl std::error::Error for ListenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ListenError::KeyHookError(code) | ListenError::MouseHookError(code) => {
+                Some(OsError::from_code(*code)?)
+            }
+            ListenError::Reentrant => None,
+        }
+    }
+}
+
+/// Marking an error when a grabber could not be installed
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum GrabError {
@@ -20,8 +55,74 @@ pub enum GrabError {
     MouseHookError(u32),
     SimulateError,
     IoError(std::io::Error),
+    /// `listen` or `grab` was called from inside a callback, while the hooks
+    /// of the thread dispatch an event. Other calls on a thread that already
+    /// listens share its hooks.
+    Reentrant,
+}
+
+impl Display for GrabError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GrabError::KeyHookError(code) => hook_error(f, "keyboard", *code),
+            GrabError::MouseHookError(code) => hook_error(f, "mouse", *code),
+            GrabError::SimulateError => write!(f, "Could not simulate event"),
+            GrabError::IoError(error) => write!(f, "I/O error: {}", error),
+            GrabError::Reentrant => reentrant(f),
+        }
+    }
+}
+
+impl std::error::Error for GrabError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GrabError::KeyHookError(code) | GrabError::MouseHookError(code) => {
+                Some(OsError::from_code(*code)?)
+            }
+            GrabError::IoError(error) => Some(error),
+            GrabError::SimulateError | GrabError::Reentrant => None,
+        }
+    }
+}
+
+fn hook_error(f: &mut fmt::Formatter, hook: &str, code: u32) -> fmt::Result {
+    write!(f, "Could not install the {} hook: ", hook)?;
+    os_error(f, code)
+}
+
+fn reentrant(f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+        f,
+        "Can't listen or grab from inside a callback of the same thread"
+    )
+}
+
+/// Writes the message of a `GetLastError` code, or only the code if we
+/// don't know it.
+fn os_error(f: &mut fmt::Formatter, code: u32) -> fmt::Result {
+    match OsError::from_code(code) {
+        Some(error) => write!(f, "{}", error),
+        None => write!(f, "OS error {}", code),
+    }
 }
 
+/// A Win32 error code we know the meaning of, as returned by `GetLastError`.
+/// The `source` of the errors carrying such a code.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct OsError {
+    pub code: u32,
+    pub message: &'static str,
+}
+
+impl Display for OsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (OS error {})", self.message, self.code)
+    }
+}
+
+impl std::error::Error for OsError {}
+
 #[non_exhaustive]
 #[derive(Debug)]
 pub enum DisplayError {
@@ -41,17 +142,302 @@ impl From<std::io::Error> for GrabError {
     }
 }
 
-/// Marking an error when we tried to simulate and event
-#[derive(Debug)]
-pub struct SimulateError;
+/// Marking an error when we tried to simulate an event
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum SimulateError {
+    /// The key has no virtual key code to send.
+    UnmappedKey { event_type: EventType },
+    /// The button is not one `SendInput` can press.
+    UnmappedButton { event_type: EventType },
+    /// A wheel delta or a relative move doesn't fit in a `SendInput` input.
+    OutOfRange { event_type: EventType },
+    /// The virtual screen has no size, so an absolute move can't be scaled.
+    NoDisplay { event_type: EventType },
+    /// `SendInput` didn't inject the inputs, because UIPI blocked them or the
+    /// secure desktop is shown. `event_type` is `None` for `simulate_text`.
+    /// `os_error` is the code from `GetLastError`, which Windows leaves to 0
+    /// when UIPI is the cause.
+    Blocked {
+        event_type: Option<EventType>,
+        os_error: u32,
+    },
+}
+
+impl SimulateError {
+    /// The event that could not be simulated.
+    pub fn event_type(&self) -> Option<&EventType> {
+        match self {
+            SimulateError::UnmappedKey { event_type }
+            | SimulateError::UnmappedButton { event_type }
+            | SimulateError::OutOfRange { event_type }
+            | SimulateError::NoDisplay { event_type } => Some(event_type),
+            SimulateError::Blocked { event_type, .. } => event_type.as_ref(),
+        }
+    }
+
+    /// The code from `GetLastError`, when the OS refused the input.
+    pub fn os_error(&self) -> Option<u32> {
+        match self {
+            SimulateError::Blocked { os_error, .. } => Some(*os_error),
+            _ => None,
+        }
+    }
+}
 
 impl Display for SimulateError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Could not simulate event")
+        match self.event_type() {
+            Some(event_type) => write!(f, "Could not simulate {:?}: ", event_type)?,
+            None => write!(f, "Could not simulate text: ")?,
+        }
+        match self {
+            SimulateError::UnmappedKey { .. } => write!(f, "the key has no virtual key code"),
+            SimulateError::UnmappedButton { .. } => {
+                write!(f, "the button can't be simulated")
+            }
+            SimulateError::OutOfRange { .. } => write!(f, "the value is out of range"),
+            SimulateError::NoDisplay { .. } => write!(f, "the virtual screen has no size"),
+            SimulateError::Blocked { os_error: 0, .. } => write!(f, "the input was blocked"),
+            SimulateError::Blocked { os_error: code, .. } => {
+                write!(f, "the input was blocked: ")?;
+                os_error(f, *code)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SimulateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SimulateError::Blocked { os_error, .. } => Some(OsError::from_code(*os_error)?),
+            _ => None,
+        }
+    }
+}
+
+/// Marking an error when a batch of events could not be simulated
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum BatchError {
+    /// The event at `index` can't be simulated, so nothing was sent.
+    InvalidEvent { index: usize, error: SimulateError },
+    /// The OS only injected the first `injected` of the `total` inputs,
+    /// for instance because UIPI blocked the rest. An event usually takes one
+    /// input, but a wheel event scrolling both ways takes two.
+    Blocked { injected: usize, total: usize },
+}
+
+impl Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BatchError::InvalidEvent { index, .. } => {
+                write!(f, "Event {} can't be simulated", index)
+            }
+            BatchError::Blocked { injected, total } => {
+                write!(f, "Only {} of {} inputs were injected", injected, total)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BatchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BatchError::InvalidEvent { error, .. } => Some(error),
+            BatchError::Blocked { .. } => None,
+        }
+    }
+}
+
+/// Marking an error when a string could not be parsed as a `Hotkey`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseHotkeyError {
+    MissingKey,
+    UnknownKey(ParseNameError),
+    UnknownModifier(String),
+    DuplicateModifier(HotkeyModifier),
+}
+
+impl Display for ParseHotkeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseHotkeyError::MissingKey => write!(f, "Hotkey has no key"),
+            ParseHotkeyError::UnknownKey(error) => write!(f, "Invalid key: {}", error),
+            ParseHotkeyError::UnknownModifier(name) => write!(f, "Unknown modifier {:?}", name),
+            ParseHotkeyError::DuplicateModifier(modifier) => {
+                write!(f, "Modifier {:?} is given twice", modifier)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseHotkeyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseHotkeyError::UnknownKey(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Marking an error when a string is not the name of a `Key` or a `Button`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ParseNameError {
+    pub name: String,
+    /// The closest valid names, best first.
+    pub suggestions: Vec<&'static str>,
+}
+
+impl Display for ParseNameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown name {:?}", self.name)?;
+        if let Some((last, others)) = self.suggestions.split_last() {
+            write!(f, ", did you mean ")?;
+            if !others.is_empty() {
+                let others: Vec<String> = others.iter().map(|name| format!("{:?}", name)).collect();
+                write!(f, "{} or ", others.join(", "))?;
+            }
+            write!(f, "{:?}?", last)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseNameError {}
+
+/// Marking an error when a `Keymap` could not be loaded
+#[cfg(feature = "config")]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum KeymapError {
+    /// `entry` is not a valid `Hotkey`.
+    Hotkey {
+        entry: String,
+        error: ParseHotkeyError,
+    },
+    /// The file extension is neither `.toml` nor `.json`.
+    UnknownFormat(PathBuf),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    IoError(std::io::Error),
+}
+
+#[cfg(feature = "config")]
+impl Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeymapError::Hotkey { entry, error } => {
+                write!(f, "Invalid entry {:?}: {}", entry, error)
+            }
+            KeymapError::UnknownFormat(path) => {
+                write!(f, "Unknown keymap format for {}", path.display())
+            }
+            KeymapError::Toml(error) => write!(f, "Invalid TOML keymap: {}", error),
+            KeymapError::Json(error) => write!(f, "Invalid JSON keymap: {}", error),
+            KeymapError::IoError(error) => write!(f, "Could not read keymap: {}", error),
+        }
     }
 }
 
-impl std::error::Error for SimulateError {}
+#[cfg(feature = "config")]
+impl std::error::Error for KeymapError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            KeymapError::Hotkey { error, .. } => Some(error),
+            KeymapError::UnknownFormat(_) => None,
+            KeymapError::Toml(error) => Some(error),
+            KeymapError::Json(error) => Some(error),
+            KeymapError::IoError(error) => Some(error),
+        }
+    }
+}
+
+#[cfg(feature = "config")]
+impl From<toml::de::Error> for KeymapError {
+    fn from(err: toml::de::Error) -> KeymapError {
+        KeymapError::Toml(err)
+    }
+}
+
+#[cfg(feature = "config")]
+impl From<serde_json::Error> for KeymapError {
+    fn from(err: serde_json::Error) -> KeymapError {
+        KeymapError::Json(err)
+    }
+}
+
+#[cfg(feature = "config")]
+impl From<std::io::Error> for KeymapError {
+    fn from(err: std::io::Error) -> KeymapError {
+        KeymapError::IoError(err)
+    }
+}
+
+/// Marking an error when a recording could not be written or read
+#[cfg(feature = "record")]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RecordError {
+    /// The data doesn't start with a recording header.
+    InvalidHeader,
+    /// The recording was written in a version of the format this one can't
+    /// read.
+    UnsupportedVersion(u16),
+    Json(serde_json::Error),
+    Binary(bincode::Error),
+    IoError(std::io::Error),
+}
+
+#[cfg(feature = "record")]
+impl Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordError::InvalidHeader => write!(f, "Not a recording"),
+            RecordError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported recording version {}", version)
+            }
+            RecordError::Json(error) => write!(f, "Invalid JSON recording: {}", error),
+            RecordError::Binary(error) => write!(f, "Invalid binary recording: {}", error),
+            RecordError::IoError(error) => write!(f, "Could not access recording: {}", error),
+        }
+    }
+}
+
+#[cfg(feature = "record")]
+impl std::error::Error for RecordError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RecordError::InvalidHeader | RecordError::UnsupportedVersion(_) => None,
+            RecordError::Json(error) => Some(error),
+            RecordError::Binary(error) => Some(error),
+            RecordError::IoError(error) => Some(error),
+        }
+    }
+}
+
+#[cfg(feature = "record")]
+impl From<serde_json::Error> for RecordError {
+    fn from(err: serde_json::Error) -> RecordError {
+        RecordError::Json(err)
+    }
+}
+
+#[cfg(feature = "record")]
+impl From<bincode::Error> for RecordError {
+    fn from(err: bincode::Error) -> RecordError {
+        RecordError::Binary(err)
+    }
+}
+
+#[cfg(feature = "record")]
+impl From<std::io::Error> for RecordError {
+    fn from(err: std::io::Error) -> RecordError {
+        RecordError::IoError(err)
+    }
+}
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
@@ -163,12 +549,78 @@ pub enum Key {
     Kp8,
     Kp9,
     KpDelete,
+    /// The Fn key, which is handled by the keyboard itself on Windows.
     Function,
+    /// The numpad separator (`VK_SEPARATOR`), found on some non-US keypads.
+    KpSeparator,
+    /// The key between AltGr and right Control that opens the context menu
+    /// (`VK_APPS`).
+    ContextMenu,
+    Sleep,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    Cancel,
+    Clear,
+    Select,
+    Print,
+    Execute,
+    Help,
+    /// Also Hangul mode on Korean keyboards.
+    Kana,
+    ImeOn,
+    Junja,
+    Final,
+    /// Also Hanja mode on Korean keyboards.
+    Kanji,
+    ImeOff,
+    Convert,
+    NonConvert,
+    Accept,
+    ModeChange,
+    ImeProcess,
+    BrowserBack,
+    BrowserForward,
+    BrowserRefresh,
+    BrowserStop,
+    BrowserSearch,
+    BrowserFavorites,
+    BrowserHome,
+    VolumeMute,
+    VolumeDown,
+    VolumeUp,
+    MediaNextTrack,
+    MediaPrevTrack,
+    MediaStop,
+    MediaPlayPause,
+    LaunchMail,
+    LaunchMediaSelect,
+    LaunchApp1,
+    LaunchApp2,
+    /// `VK_OEM_8`, whose character depends on the layout (`!` on AZERTY).
+    Oem8,
+    Attn,
+    CrSel,
+    ExSel,
+    EraseEof,
+    Play,
+    Zoom,
+    Pa1,
+    OemClear,
     Unknown(u32),
 }
 
 /// Standard mouse buttons
-/// Some mice have more than 3 buttons. These are not defined, and different
+/// Some mice have more than 5 buttons. These are not defined, and different
 /// OSs will give different `Button::Unknown` values.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
@@ -176,9 +628,46 @@ pub enum Button {
     Left,
     Right,
     Middle,
+    /// The first side button (`XBUTTON1`), which browsers use to go back.
+    Back,
+    /// The second side button (`XBUTTON2`), which browsers use to go forward.
+    Forward,
     Unknown(u8),
 }
 
+impl Button {
+    /// The button of a `WM_XBUTTONDOWN` or `WM_XBUTTONUP` message, from the
+    /// high word of its `mouseData`.
+    pub fn from_x_button(code: u16) -> Button {
+        match code {
+            1 => Button::Back,
+            2 => Button::Forward,
+            code => Button::Unknown(code as u8),
+        }
+    }
+
+    /// The `mouseData` to simulate the button with `MOUSEEVENTF_XDOWN` and
+    /// `MOUSEEVENTF_XUP`, `None` for the left, right and middle buttons.
+    pub fn x_button(self) -> Option<u16> {
+        match self {
+            Button::Back => Some(1),
+            Button::Forward => Some(2),
+            Button::Unknown(code) => Some(code.into()),
+            Button::Left | Button::Right | Button::Middle => None,
+        }
+    }
+
+    /// Older versions reported the side buttons as `Unknown(1)` and
+    /// `Unknown(2)`: this turns them into `Back` and `Forward`, for instance
+    /// when reading events that were serialized back then.
+    pub fn normalize(self) -> Button {
+        match self {
+            Button::Unknown(code) => Button::from_x_button(code.into()),
+            button => button,
+        }
+    }
+}
+
 /// In order to manage different OSs, the current EventType choices are a mix and
 /// match to account for all possible events.
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -192,20 +681,45 @@ pub enum EventType {
     ButtonPress(Button),
     ButtonRelease(Button),
     /// Values in pixels. `EventType::MouseMove{x: 0, y: 0}` corresponds to the
-    /// top left corner, with x increasing downward and y increasing rightward
+    /// top left corner of the primary monitor, with x increasing rightward and
+    /// y increasing downward. Monitors left of or above the primary one have
+    /// negative coordinates.
     MouseMove {
         x: f64,
         y: f64,
     },
     /// `delta_y` represents vertical scroll and `delta_x` represents horizontal scroll.
     /// Positive values correspond to scrolling up or right and negative values
-    /// correspond to scrolling down or left
+    /// correspond to scrolling down or left. The hook reports raw deltas,
+    /// `ScrollAccumulator` turns them into lines or pages.
     Wheel {
         delta_x: i64,
         delta_y: i64,
+        #[cfg_attr(feature = "serialize", serde(default))]
+        unit: WheelUnit,
+    },
+    /// A move by a number of pixels rather than to a position, as games and
+    /// 3D tools expect. The pointer speed and acceleration settings of the
+    /// user apply when it is simulated. The hook only reports positions:
+    /// listeners receive it with `ListenOptions::relative_moves`.
+    MouseMoveRelative {
+        dx: i64,
+        dy: i64,
     },
 }
 
+/// What the deltas of `EventType::Wheel` count.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum WheelUnit {
+    /// Notches of a regular wheel.
+    #[default]
+    Notches,
+    /// What Windows reports, 120 per notch of a regular wheel. Precision
+    /// touchpads and free spinning wheels send fractions of that.
+    Raw,
+}
+
 /// When events arrive from the OS they get some additional information added from
 /// EventType, which is the time when this event was received, and the name Option
 /// which contains what characters should be emmitted from that event. This relies
@@ -218,6 +732,121 @@ pub struct Event {
     pub time: SystemTime,
     pub name: Option<String>,
     pub event_type: EventType,
+    /// The modifiers held and the lock keys toggled on right after this
+    /// event, as seen by the hook. Empty for events that were not received
+    /// from the OS.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub modifiers: Modifiers,
+    /// What the hook reported besides the event type. `None` for events that
+    /// were not received from the OS.
+    pub raw: Option<RawEventInfo>,
+}
+
+impl Event {
+    /// Where the event comes from, `None` for events that were not received
+    /// from the OS.
+    pub fn origin(&self) -> Option<EventOrigin> {
+        self.raw.map(|raw| raw.origin)
+    }
+
+    /// Whether the event was injected rather than coming from a device.
+    pub fn is_synthetic(&self) -> bool {
+        self.origin()
+            .is_some_and(|origin| origin != EventOrigin::Hardware)
+    }
+}
+
+/// Where an event received from the OS comes from, told apart with the
+/// injected flag and the injection signature found in `dwExtraInfo`
+/// (see `set_injection_signature`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum EventOrigin {
+    /// Sent by a device.
+    #[default]
+    Hardware,
+    /// Injected by another process, or by this one through other means than
+    /// this crate.
+    Injected,
+    /// Injected by this process with `simulate` and its variants.
+    SelfInjected,
+    /// Injected by this process in place of an event that a grabber changed.
+    Replacement,
+}
+
+/// Details of an event as reported by the low level keyboard and mouse hooks,
+/// from `KBDLLHOOKSTRUCT` and `MSLLHOOKSTRUCT`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct RawEventInfo {
+    /// Hardware scan code of the key, 0 for mouse events.
+    pub scan_code: u32,
+    /// `LLKHF_EXTENDED`: the key comes from the extended part of the keyboard,
+    /// like the numpad Enter, the right Ctrl or the arrow keys.
+    pub extended: bool,
+    /// `LLKHF_INJECTED` or `LLMHF_INJECTED`: the event was injected, for
+    /// instance with `SendInput`, rather than coming from a device.
+    pub injected: bool,
+    /// `LLKHF_LOWER_IL_INJECTED` or `LLMHF_LOWER_IL_INJECTED`: the event was
+    /// injected by a process running at a lower integrity level.
+    pub lower_il_injected: bool,
+    /// `LLKHF_ALTDOWN`: Alt was held when the key event happened.
+    pub alt_down: bool,
+    /// `dwExtraInfo`, set by whoever injected the event.
+    pub extra_info: usize,
+    /// OS timestamp of the event in milliseconds, comparable to `GetMessageTime`.
+    pub time: u32,
+    /// Decoded from `injected` and `extra_info`.
+    pub origin: EventOrigin,
+}
+
+/// A Set 1 scan code: what the keyboard reports for a physical key, whatever
+/// the layout. Keys of the extended part of the keyboard are prefixed with
+/// `E0`, which Windows reports as the `LLKHF_EXTENDED` flag.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct ScanCode {
+    pub code: u16,
+    pub extended: bool,
+}
+
+impl ScanCode {
+    pub const fn new(code: u16, extended: bool) -> ScanCode {
+        ScanCode { code, extended }
+    }
+}
+
+impl From<&RawEventInfo> for ScanCode {
+    fn from(raw: &RawEventInfo) -> ScanCode {
+        ScanCode::new(raw.scan_code as u16, raw.extended)
+    }
+}
+
+/// A rectangle in virtual screen coordinates, in pixels. Like the Win32
+/// `RECT`, `right` and `bottom` are excluded. Monitors left of or above the
+/// primary one have negative coordinates.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Rect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+/// A monitor attached to the desktop, as returned by `monitors`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct MonitorInfo {
+    pub bounds: Rect,
+    /// The bounds minus the taskbar and docked toolbars.
+    pub work_area: Rect,
+    pub primary: bool,
+    /// Such as `\\.\DISPLAY1`.
+    pub device_name: String,
+    /// The effective DPI divided by 96, 1.5 for a 150% scale.
+    pub scale: f64,
 }
 
 /// We can define a dummy Keyboard, that we will use to detect
@@ -226,8 +855,7 @@ pub struct Event {
 /// Caveat : This is layout dependent. If your app needs to support
 /// layout switching don't use this !
 /// Caveat: On Linux, the dead keys mechanism is not implemented.
-/// Caveat: Only shift and dead keys are implemented, Alt+unicode code on windows
-/// won't work.
+/// Caveat: Alt+unicode code on windows won't work.
 ///
 /// ```no_run
 /// use rdev::{Keyboard, EventType, Key, KeyboardState};
@@ -242,6 +870,60 @@ pub trait KeyboardState {
     /// if we were to hit said key.
     fn add(&mut self, event_type: &EventType) -> Option<String>;
 
-    /// Resets the keyboard state as if we never touched it (no shift, caps_lock and so on)
+    /// Resets the keyboard state as if we never touched it (no modifiers, lock
+    /// keys or pending dead key)
     fn reset(&mut self);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_x_buttons() {
+        assert_eq!(Button::from_x_button(1), Button::Back);
+        assert_eq!(Button::from_x_button(2), Button::Forward);
+        assert_eq!(Button::from_x_button(3), Button::Unknown(3));
+        for button in [Button::Back, Button::Forward, Button::Unknown(3)] {
+            assert_eq!(Button::from_x_button(button.x_button().unwrap()), button);
+        }
+        assert_eq!(Button::Left.x_button(), None);
+        assert_eq!(Button::Unknown(1).x_button(), Button::Back.x_button());
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(Button::Unknown(1).normalize(), Button::Back);
+        assert_eq!(Button::Unknown(2).normalize(), Button::Forward);
+        assert_eq!(Button::Unknown(7).normalize(), Button::Unknown(7));
+        assert_eq!(Button::Middle.normalize(), Button::Middle);
+    }
+
+    #[test]
+    fn test_simulate_error() {
+        let error = SimulateError::UnmappedKey {
+            event_type: EventType::KeyPress(Key::Unknown(7)),
+        };
+        assert_eq!(
+            error.event_type(),
+            Some(&EventType::KeyPress(Key::Unknown(7)))
+        );
+        assert_eq!(error.os_error(), None);
+        assert_eq!(
+            error.to_string(),
+            "Could not simulate KeyPress(Unknown(7)): the key has no virtual key code"
+        );
+
+        let error = SimulateError::Blocked {
+            event_type: None,
+            os_error: 5,
+        };
+        assert_eq!(error.event_type(), None);
+        assert_eq!(error.os_error(), Some(5));
+        assert_eq!(
+            error.to_string(),
+            "Could not simulate text: the input was blocked: Access is denied (OS error 5)"
+        );
+        assert!(matches!(GrabError::from(error), GrabError::SimulateError));
+    }
+}
@@ -1,27 +1,9 @@
 use serial_test::serial;
 use std::error::Error;
-use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
-use std::sync::LazyLock;
-use std::sync::Mutex;
+use std::sync::mpsc::RecvTimeoutError;
 use std::thread;
 use std::time::Duration;
-use win_binder::{grab, listen, simulate, Event, EventType, Key};
-
-type EventChannel = (Mutex<Sender<Event>>, Mutex<Receiver<Event>>);
-
-static EVENT_CHANNEL: LazyLock<EventChannel> = LazyLock::new(|| {
-    let (send, recv) = channel();
-    (Mutex::new(send), Mutex::new(recv))
-});
-
-fn send_event(event: Event) {
-    EVENT_CHANNEL
-        .0
-        .lock()
-        .expect("Failed to unlock Mutex")
-        .send(event)
-        .expect("Receiving end of EVENT_CHANNEL was closed");
-}
+use win_binder::{events, simulate, spawn_grab, Event, EventType, Key};
 
 fn grab_tab(event: Event) -> Option<Event> {
     match event.event_type {
@@ -38,20 +20,9 @@ fn test_grab() -> Result<(), Box<dyn Error>> {
     // otherwise, test fails due to KeyRelease(Return)
     thread::sleep(Duration::from_millis(300));
 
-    // spawn new thread because listen blocks
-    let _listener = thread::spawn(move || {
-        listen(send_event).expect("Could not listen");
-    });
-    // Make sure grab ends up on top of listen so it can properly discard.
-    thread::sleep(Duration::from_secs(1));
-    let _grab = thread::spawn(move || {
-        grab(grab_tab).expect("Could not grab");
-    });
-
-    let recv = EVENT_CHANNEL.1.lock().expect("Failed to unlock Mutex");
-
-    // Wait for listen to start
-    thread::sleep(Duration::from_secs(1));
+    let recv = events().expect("Could not listen");
+    // Started after the listener, so grab ends up on top of it and can discard.
+    let _grab = spawn_grab(grab_tab).expect("Could not grab");
 
     let event_type = EventType::KeyPress(Key::KeyS);
     let event_type2 = EventType::KeyRelease(Key::KeyS);
@@ -1,39 +1,17 @@
 use serial_test::serial;
 use std::error::Error;
 use std::iter::Iterator;
-use std::sync::mpsc::{channel, Receiver, Sender};
-use std::sync::LazyLock;
-use std::sync::Mutex;
 use std::thread;
 use std::time::Duration;
-use win_binder::{listen, simulate, Button, Event, EventType, Key};
+use win_binder::{events, simulate, Button, EventType, Key, WheelUnit};
 
-type EventChannel = (Mutex<Sender<Event>>, Mutex<Receiver<Event>>);
-
-static EVENT_CHANNEL: LazyLock<EventChannel> = LazyLock::new(|| {
-    let (send, recv) = channel();
-    (Mutex::new(send), Mutex::new(recv))
-});
-
-fn send_event(event: Event) {
-    EVENT_CHANNEL
-        .0
-        .lock()
-        .expect("Failed to unlock Mutex")
-        .send(event)
-        .expect("Receiving end of EVENT_CHANNEL was closed");
-}
-
-fn sim_then_listen(events: &mut dyn Iterator<Item = EventType>) -> Result<(), Box<dyn Error>> {
-    // spawn new thread because listen blocks
-    let _listener = thread::spawn(move || {
-        listen(send_event).expect("Could not listen");
-    });
+fn sim_then_listen(
+    events_to_send: &mut dyn Iterator<Item = EventType>,
+) -> Result<(), Box<dyn Error>> {
+    let recv = events().expect("Could not listen");
     let second = Duration::from_millis(1000);
-    thread::sleep(second);
 
-    let recv = EVENT_CHANNEL.1.lock()?;
-    for event in events {
+    for event in events_to_send {
         simulate(&event)?;
         let recieved_event = recv.recv_timeout(second).expect("No events to recieve");
         assert_eq!(recieved_event.event_type, event);
@@ -55,13 +33,21 @@ fn test_listen_and_simulate() -> Result<(), Box<dyn Error>> {
         EventType::KeyRelease(Key::KeyS),
         EventType::ButtonPress(Button::Right),
         EventType::ButtonRelease(Button::Right),
+        // The hook reports raw deltas.
         EventType::Wheel {
             delta_x: 0,
-            delta_y: 1,
+            delta_y: 120,
+            unit: WheelUnit::Raw,
         },
         EventType::Wheel {
             delta_x: 0,
-            delta_y: -1,
+            delta_y: -120,
+            unit: WheelUnit::Raw,
+        },
+        EventType::Wheel {
+            delta_x: 40,
+            delta_y: 0,
+            unit: WheelUnit::Raw,
         },
     ]
     .into_iter();